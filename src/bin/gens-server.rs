@@ -0,0 +1,102 @@
+//! Serves the children of a persistent root `Id` over TCP or a Unix socket.
+//! See [`gens::server`].
+
+use std::net::TcpListener;
+use std::process::ExitCode;
+
+use gens::server::Server;
+use gens::{Id, PersistentId, DEFAULT_BLOCK_SIZE};
+
+const USAGE: &str = "\
+usage: gens-server <state-file> (--tcp <address> | --unix <path>) [options]
+
+options:
+    --namespace <name>   derive the root from a namespace
+    --block-size <n>     how many children to reserve on disk at once";
+
+enum Address {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(String),
+}
+
+struct Args {
+    state: String,
+    address: Address,
+    namespace: Option<String>,
+    block_size: u64,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut state = None;
+    let mut address = None;
+    let mut namespace = None;
+    let mut block_size = DEFAULT_BLOCK_SIZE;
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for `{arg}`"))
+        };
+        match arg.as_str() {
+            "--tcp" => address = Some(Address::Tcp(value()?)),
+            #[cfg(unix)]
+            "--unix" => address = Some(Address::Unix(value()?)),
+            "--namespace" => namespace = Some(value()?),
+            "--block-size" => {
+                block_size = value()?
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or("block size must be a positive integer")?;
+            }
+            _ if arg.starts_with('-') || state.is_some() => {
+                return Err(format!("unexpected argument `{arg}`"));
+            }
+            _ => state = Some(arg),
+        }
+    }
+
+    Ok(Args {
+        state: state.ok_or("missing state file")?,
+        address: address.ok_or("missing address")?,
+        namespace,
+        block_size,
+    })
+}
+
+fn run(args: Args) -> std::io::Result<()> {
+    let root = match &args.namespace {
+        Some(namespace) => Id::root_in_namespace(namespace.as_bytes()),
+        None => Id::root(),
+    };
+    let server = Server::new(PersistentId::open_with_block_size(
+        &args.state,
+        root,
+        args.block_size,
+    )?);
+
+    match &args.address {
+        Address::Tcp(address) => server.serve_tcp(&TcpListener::bind(address)?),
+        #[cfg(unix)]
+        Address::Unix(path) => server.serve_unix(&std::os::unix::net::UnixListener::bind(path)?),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
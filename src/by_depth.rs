@@ -0,0 +1,56 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+use crate::{Fnv, Id};
+
+/// Wraps an [`Id`] to compare, order and hash it by depth alone.
+///
+/// Useful to e.g. process `Id`s shallowest-first in a
+/// [`BinaryHeap`](https://doc.rust-lang.org/std/collections/struct.BinaryHeap.html).
+/// Note that distinct `Id`s at the same depth compare equal.
+///
+/// ```
+/// use gens::{ByDepth, Id};
+///
+/// let mut root = Id::root();
+/// let a = root.next_id();
+/// let b = root.next_id();
+///
+/// assert_ne!(a, b);
+/// assert_eq!(ByDepth(a), ByDepth(b));
+/// assert!(ByDepth(root) < ByDepth(Id::root().next_id()));
+/// ```
+pub struct ByDepth<D = Fnv>(pub Id<D>);
+
+impl<D> PartialEq for ByDepth<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.depth == other.0.depth
+    }
+}
+
+impl<D> Eq for ByDepth<D> {}
+
+impl<D> PartialOrd for ByDepth<D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<D> Ord for ByDepth<D> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp_depth(&other.0)
+    }
+}
+
+impl<D> Hash for ByDepth<D> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.depth.hash(state)
+    }
+}
+
+impl<D> fmt::Debug for ByDepth<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ByDepth").field(&self.0).finish()
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{DecodeError, Id, IdDeriver, Overflow, Version};
+
+/// The current format of [`Id::to_bytes`].
+const FORMAT: u8 = 1;
+
+/// The length of [`Id::to_bytes`]'s output.
+pub const ENCODED_LEN: usize = 60;
+
+impl<D: IdDeriver> Id<D> {
+    /// Encode the full state of this `Id`, so it can be stored (e.g. in a
+    /// file, a database, or a network message) and later resumed with
+    /// [`Id::from_bytes`].
+    ///
+    /// The layout is fixed and little-endian:
+    ///
+    /// | Offset | Length | Field                             |
+    /// |--------|--------|-----------------------------------|
+    /// | 0      | 1      | Format (currently 1)              |
+    /// | 1      | 1      | [`Version`]                       |
+    /// | 2      | 1      | [`Overflow`] (0: wrap, 1: spill)  |
+    /// | 3      | 8      | [`IdDeriver::TAG`]                |
+    /// | 11     | 8      | ID                                |
+    /// | 19     | 16     | Parent's final ID                 |
+    /// | 35     | 4      | Depth                             |
+    /// | 39     | 8      | Number of children                |
+    /// | 47     | 8      | Epoch (see [`Snapshot`])          |
+    /// | 55     | 1      | Node flag (0: none, 1: node)      |
+    /// | 56     | 4      | Node (see [`Id::root_for_node`])  |
+    ///
+    /// Future releases may add new formats, but will keep decoding this one.
+    ///
+    /// [`Snapshot`]: crate::Snapshot
+    ///
+    /// Careful: like the `Id` itself, every decoded copy generates the same
+    /// children. Only resume a stored `Id` once!
+    ///
+    /// ```
+    /// use gens::Id;
+    ///
+    /// let mut root = Id::root();
+    /// let child = root.next_id();
+    ///
+    /// let mut decoded = Id::from_bytes(&root.to_bytes()).unwrap();
+    /// assert_eq!(decoded, root);
+    /// assert_eq!(decoded.num_children(), 1);
+    /// assert_ne!(decoded.next_id(), child);
+    /// ```
+    pub fn to_bytes(&self) -> [u8; ENCODED_LEN] {
+        let mut bytes = [0; ENCODED_LEN];
+        bytes[0] = FORMAT;
+        bytes[1] = self.version as u8;
+        bytes[2] = self.overflow.repr();
+        bytes[3..11].copy_from_slice(&D::TAG.to_le_bytes());
+        bytes[11..19].copy_from_slice(&self.id.to_le_bytes());
+        bytes[19..35].copy_from_slice(&self.parent.to_le_bytes());
+        bytes[35..39].copy_from_slice(&self.depth.to_le_bytes());
+        bytes[39..47].copy_from_slice(&self.gen.to_le_bytes());
+        bytes[47..55].copy_from_slice(&self.epoch.to_le_bytes());
+        if let Some(node) = self.node {
+            bytes[55] = 1;
+            bytes[56..60].copy_from_slice(&node.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decode an `Id` encoded with [`Id::to_bytes`].
+    ///
+    /// Fails if the input is malformed, was encoded by a newer release, was
+    /// encoded with a different [`IdDeriver`], or describes an `Id` that
+    /// can't exist.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        match bytes.first() {
+            None => return Err(DecodeError::InvalidLength),
+            Some(&FORMAT) => {}
+            Some(_) => return Err(DecodeError::UnknownFormat),
+        }
+
+        let bytes: &[u8; ENCODED_LEN] = bytes.try_into().map_err(|_| DecodeError::InvalidLength)?;
+
+        let version = Version::from_repr(bytes[1]).ok_or(DecodeError::UnknownVersion)?;
+        let overflow = Overflow::from_repr(bytes[2]).ok_or(DecodeError::UnknownOverflow)?;
+
+        if u64::from_le_bytes(le(&bytes[3..11])) != D::TAG {
+            return Err(DecodeError::DeriverMismatch);
+        }
+
+        let mut id = Self::from_parts(
+            u64::from_le_bytes(le(&bytes[11..19])),
+            u128::from_le_bytes(le(&bytes[19..35])),
+            u32::from_le_bytes(le(&bytes[35..39])),
+            version,
+        );
+        id.overflow = overflow;
+        id.gen = u64::from_le_bytes(le(&bytes[39..47]));
+        id.epoch = u64::from_le_bytes(le(&bytes[47..55]));
+
+        let node = u32::from_le_bytes(le(&bytes[56..60]));
+        id.node = match bytes[55] {
+            0 if node == 0 => None,
+            1 => Some(node),
+            _ => return Err(DecodeError::Inconsistent),
+        };
+
+        id.validate()?;
+        Ok(id)
+    }
+}
+
+/// Convert a subslice of known length to an array.
+fn le<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes.try_into().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{DecodeError, Fnv, Id, IdDeriver, Overflow, Version};
+
+    #[test]
+    fn round_trip() {
+        let mut root = Id::root_with_seed(14);
+        let mut child = root.next_id();
+        child.next_id();
+        let legacy = Id::root().with_version(Version::V0);
+        let mut spilling = Id::root().with_overflow(Overflow::Spill);
+        spilling.gen = u64::MAX - 1;
+        let node = Id::root_for_node(14, 14).next_id();
+
+        for id in [root, child, legacy, spilling, node] {
+            let mut decoded = Id::from_bytes(&id.to_bytes()).unwrap();
+            assert_eq!(decoded.to_bytes(), id.to_bytes());
+            assert_eq!(decoded.total_children(), id.total_children());
+            assert_eq!(decoded.version(), id.version());
+            assert_eq!(decoded.overflow(), id.overflow());
+            assert_eq!(decoded.node(), id.node());
+
+            let mut id = id;
+            assert_eq!(decoded.next_id(), id.next_id());
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let mut root = Id::root();
+        root.next_id();
+
+        let mut expected = [0; 60];
+        expected[0] = 1;
+        expected[1] = 2;
+        expected[39] = 1;
+        assert_eq!(root.to_bytes(), expected);
+
+        let bytes = Id::root_for_node(0x0102, 0).to_bytes();
+        assert_eq!(bytes[55..], [1, 2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn invalid() {
+        struct Other;
+        impl IdDeriver for Other {
+            const TAG: u64 = 1;
+
+            fn derive(bytes: &[u8]) -> u64 {
+                Fnv::derive(bytes)
+            }
+        }
+
+        let bytes = Id::root_with_seed(1).next_id().to_bytes();
+        let decode = |bytes: &[u8]| Id::<Fnv>::from_bytes(bytes).map(|id| id.id());
+        let with = |i: usize, b: u8| {
+            let mut bytes = bytes;
+            bytes[i] = b;
+            bytes
+        };
+
+        assert_eq!(decode(&[]), Err(DecodeError::InvalidLength));
+        assert_eq!(decode(&bytes[..59]), Err(DecodeError::InvalidLength));
+        assert_eq!(
+            decode(&[bytes.as_slice(), &[0]].concat()),
+            Err(DecodeError::InvalidLength)
+        );
+        assert_eq!(decode(&with(0, 0)), Err(DecodeError::UnknownFormat));
+        assert_eq!(decode(&with(0, 2)), Err(DecodeError::UnknownFormat));
+        assert_eq!(decode(&with(1, 200)), Err(DecodeError::UnknownVersion));
+        assert_eq!(decode(&with(2, 2)), Err(DecodeError::UnknownOverflow));
+        assert_eq!(decode(&with(3, 1)), Err(DecodeError::DeriverMismatch));
+        assert_eq!(
+            Id::<Other>::from_bytes(&bytes).map(|id| id.id()),
+            Err(DecodeError::DeriverMismatch)
+        );
+
+        // A root with a parent
+        assert_eq!(decode(&with(35, 0)), Err(DecodeError::Inconsistent));
+        // A wrapping `Id` past `u32::MAX` children
+        assert_eq!(decode(&with(43, 1)), Err(DecodeError::Inconsistent));
+        // An unknown node flag, or a node without the flag
+        assert_eq!(decode(&with(55, 2)), Err(DecodeError::Inconsistent));
+        assert_eq!(decode(&with(56, 1)), Err(DecodeError::Inconsistent));
+    }
+}
@@ -0,0 +1,112 @@
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::{Bound, RangeBounds, RangeInclusive};
+
+use crate::{Fnv, Id, IdDeriver};
+
+/// An iterator over a range of an [`Id`]'s children.
+///
+/// Created by [`Id::children`].
+pub struct Children<'a, D = Fnv> {
+    parent: &'a Id<D>,
+    gens: RangeInclusive<u32>,
+}
+
+impl<'a, D> Children<'a, D> {
+    pub(crate) fn new<R: RangeBounds<u32>>(parent: &'a Id<D>, gens: R) -> Self {
+        let start = match gens.start_bound() {
+            Bound::Included(&start) => Some(start),
+            Bound::Excluded(&start) => start.checked_add(1),
+            Bound::Unbounded => Some(1),
+        };
+
+        let end = match gens.end_bound() {
+            Bound::Included(&end) => Some(end),
+            Bound::Excluded(&end) => end.checked_sub(1),
+            Bound::Unbounded => Some(u32::MAX),
+        };
+
+        #[allow(clippy::reversed_empty_ranges)]
+        let gens = match (start, end) {
+            (Some(start), Some(end)) => start..=end,
+            _ => 1..=0,
+        };
+
+        Children { parent, gens }
+    }
+}
+
+impl<D: IdDeriver> Iterator for Children<'_, D> {
+    type Item = Id<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.gens.next().map(|gen| self.parent.child(gen))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.gens.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.gens.nth(n).map(|gen| self.parent.child(gen))
+    }
+}
+
+impl<D: IdDeriver> DoubleEndedIterator for Children<'_, D> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.gens.next_back().map(|gen| self.parent.child(gen))
+    }
+}
+
+impl<D: IdDeriver> FusedIterator for Children<'_, D> {}
+
+impl<D> fmt::Debug for Children<'_, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Children")
+            .field("parent", self.parent)
+            .field("gens", &self.gens)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::ops::Bound;
+
+    use crate::Id;
+
+    #[test]
+    fn matches_sequential() {
+        let mut root = Id::root_in_namespace(b"children");
+        let generated: Vec<_> = (0..100).map(|_| root.next_id()).collect();
+
+        for (gen, child) in (1..).zip(&generated) {
+            assert_eq!(&root.child_at(gen), child);
+        }
+
+        assert_eq!(root.children(..=100).collect::<Vec<_>>(), generated);
+        assert_eq!(root.children(11..21).collect::<Vec<_>>(), generated[10..20]);
+        assert_eq!(root.children(..).nth(41).as_ref(), generated.get(41));
+
+        let mut reversed: Vec<_> = root.children(1..=100).rev().collect();
+        reversed.reverse();
+        assert_eq!(reversed, generated);
+    }
+
+    #[test]
+    fn ranges() {
+        let root = Id::root();
+
+        assert_eq!(root.children(..).size_hint().0, u32::MAX as usize);
+        assert_eq!(root.children(0..=0).count(), 1);
+        assert_eq!(root.children(5..5).count(), 0);
+        assert_eq!(root.children(..0).count(), 0);
+        assert_eq!(root.children(u32::MAX..).count(), 1);
+        assert_eq!(
+            root.children((Bound::Excluded(u32::MAX), Bound::Unbounded))
+                .count(),
+            0
+        );
+        assert_eq!(root.children(1..=3).next_back(), Some(root.child_at(3)));
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{ClusterError, FinalId, Id, IdDeriver, Version};
+
+/// How far a node is shifted into the final ID's of its tree.
+pub(crate) const NODE_SHIFT: u32 = 96;
+
+/// The bits of a node's final ID's that are derived as usual.
+pub(crate) const HASH_MASK: u128 = (1 << NODE_SHIFT) - 1;
+
+impl Id {
+    /// Create the root `Id` of a node in a cluster, e.g. one of several
+    /// machines generating ID's independently. See [`Cluster`].
+    ///
+    /// The top 32 bits of the final ID of every `Id` in the tree are the
+    /// node, so trees rooted at different nodes are guaranteed to be
+    /// disjoint. The other 96 bits are derived as usual.
+    ///
+    /// `cluster_seed` sets apart the trees of different clusters, like
+    /// [`Id::root_with_seed`], but only probabilistically.
+    ///
+    /// ```
+    /// use gens::Id;
+    ///
+    /// let mut a = Id::root_for_node(0, 42);
+    /// let mut b = Id::root_for_node(1, 42);
+    ///
+    /// assert_eq!(a.next_id().id() >> 96, 0);
+    /// assert_eq!(b.next_id().id() >> 96, 1);
+    /// assert_eq!(b.node(), Some(1));
+    /// ```
+    pub fn root_for_node(node: u32, cluster_seed: u128) -> Self {
+        Self::root_for_node_with_deriver(node, cluster_seed)
+    }
+}
+
+impl<D: IdDeriver> Id<D> {
+    /// Create the root `Id` of a node in a cluster, using a custom
+    /// [`IdDeriver`]. See [`Id::root_for_node`].
+    pub fn root_for_node_with_deriver(node: u32, cluster_seed: u128) -> Self {
+        let mut bytes = [0; 20];
+        bytes[..16].copy_from_slice(&cluster_seed.to_le_bytes());
+        bytes[16..].copy_from_slice(&node.to_le_bytes());
+
+        let mut id = Self::from_parts(Self::derive_root(&bytes), 0, 0, Version::LATEST);
+        id.node = Some(node);
+        id
+    }
+}
+
+impl<D> Id<D> {
+    /// Returns the node whose tree this `Id` belongs to, if it was created
+    /// with [`Id::root_for_node`].
+    pub fn node(&self) -> Option<u32> {
+        self.node
+    }
+}
+
+/// A cluster of nodes generating ID's independently, each from its own
+/// [`Id::root_for_node`] tree.
+///
+/// A cluster knows its seed and its number of nodes, numbered from 0, so it
+/// can hand out the nodes' roots, and check that an ID claims one of its
+/// nodes, e.g. when it's received from elsewhere.
+///
+/// ```
+/// use gens::{Cluster, ClusterError, Id};
+///
+/// let cluster = Cluster::new(42, 3);
+/// let mut root = cluster.root(2).unwrap();
+/// let id = root.next_id();
+/// assert_eq!(cluster.validate(&id), Ok(2));
+///
+/// let foreign = Id::root_for_node(7, 42).next_id();
+/// assert_eq!(cluster.node_of(foreign.final_id()), Err(ClusterError::NodeOutOfRange(7)));
+/// assert!(cluster.root(3).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cluster {
+    seed: u128,
+    nodes: u32,
+}
+
+impl Cluster {
+    /// Create a cluster with the given seed and number of nodes.
+    pub const fn new(seed: u128, nodes: u32) -> Self {
+        Cluster { seed, nodes }
+    }
+
+    /// Returns the seed passed to [`Id::root_for_node`].
+    pub const fn seed(&self) -> u128 {
+        self.seed
+    }
+
+    /// Returns the number of nodes.
+    pub const fn nodes(&self) -> u32 {
+        self.nodes
+    }
+
+    /// Create the root `Id` of the given node, or return an error if the
+    /// node is outside the cluster. See [`Id::root_for_node`].
+    pub fn root(&self, node: u32) -> Result<Id, ClusterError> {
+        self.root_with_deriver(node)
+    }
+
+    /// Create the root `Id` of the given node using a custom
+    /// [`IdDeriver`]. See [`Cluster::root`].
+    pub fn root_with_deriver<D: IdDeriver>(&self, node: u32) -> Result<Id<D>, ClusterError> {
+        self.check(node)?;
+        Ok(Id::root_for_node_with_deriver(node, self.seed))
+    }
+
+    /// Returns the node a final ID claims, or an error if it's outside the
+    /// cluster.
+    ///
+    /// This only looks at the bits reserved for the node: any final ID
+    /// whose top 32 bits are a valid node passes, even one that doesn't
+    /// belong to a node at all.
+    pub fn node_of(&self, id: impl Into<FinalId>) -> Result<u32, ClusterError> {
+        let node = (id.into().get() >> NODE_SHIFT) as u32;
+        self.check(node)?;
+        Ok(node)
+    }
+
+    /// Returns the node an `Id` belongs to, or an error if it doesn't belong
+    /// to a node, or to one outside the cluster.
+    pub fn validate<D>(&self, id: &Id<D>) -> Result<u32, ClusterError> {
+        let node = id.node().ok_or(ClusterError::NoNode)?;
+        self.check(node)?;
+        Ok(node)
+    }
+
+    fn check(&self, node: u32) -> Result<(), ClusterError> {
+        if node < self.nodes {
+            Ok(())
+        } else {
+            Err(ClusterError::NodeOutOfRange(node))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::{Cluster, ClusterError, Fnv, Id};
+
+    #[test]
+    fn nodes_are_disjoint() {
+        let cluster = Cluster::new(24, 4);
+        let mut set = HashSet::new();
+
+        for node in 0..4 {
+            let root = cluster.root(node).unwrap();
+            assert_eq!(root.id() >> 96, node as u128);
+            assert!(set.insert(root.id()));
+
+            let mut stack = vec![root];
+            while let Some(mut id) = stack.pop() {
+                assert_eq!(cluster.validate(&id), Ok(node));
+                assert_eq!(cluster.node_of(id.final_id()), Ok(node));
+
+                if id.depth() < 3 {
+                    for _ in 0..4 {
+                        let child = id.next_id();
+                        assert!(set.insert(child.id()));
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+        assert_eq!(set.len(), 4 * (1 + 4 + 16 + 64));
+    }
+
+    #[test]
+    fn node_survives() {
+        let mut root = Id::root_for_node(3, 24);
+        let mut child = root.next_id();
+        let mut snapshot = child.snapshot();
+
+        assert_eq!(child.next_id().node(), Some(3));
+        assert_eq!(snapshot.restore(0).next_id().node(), Some(3));
+        assert_eq!(root.lease(1).unwrap().next_id().unwrap().node(), Some(3));
+
+        let decoded = Id::<Fnv>::from_bytes(&child.to_bytes()).unwrap();
+        assert_eq!(decoded.node(), Some(3));
+        assert_eq!(decoded, child);
+    }
+
+    #[test]
+    fn validation() {
+        let cluster = Cluster::new(24, 2);
+        assert_eq!(cluster.root(2).err(), Some(ClusterError::NodeOutOfRange(2)));
+        assert_eq!(cluster.validate(&Id::root()), Err(ClusterError::NoNode));
+        assert_eq!(
+            cluster.validate(&Id::root_for_node(u32::MAX, 24)),
+            Err(ClusterError::NodeOutOfRange(u32::MAX))
+        );
+        assert_eq!(
+            cluster.node_of(u128::MAX),
+            Err(ClusterError::NodeOutOfRange(u32::MAX))
+        );
+
+        // The same node in different clusters
+        assert_ne!(
+            Id::root_for_node(0, 24).next_id(),
+            Id::root_for_node(0, 25).next_id()
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use core::hash::Hasher;
+
+use fnv::FnvHasher;
+
+/// A hash function used to derive new [`Id`](crate::Id)s.
+///
+/// `Id` is generic over its deriver, with [`Fnv`] as the default. Every root
+/// is derived from its deriver's [`TAG`](IdDeriver::TAG) and every child is
+/// hashed by its deriver, so trees built with different derivers never
+/// overlap.
+///
+/// Note: derivers must be deterministic and platform-independent, or `Id`s
+/// will be too.
+///
+/// ```
+/// use gens::{Id, IdDeriver};
+///
+/// /// FNV-1a with a custom offset basis, acting as a key.
+/// struct KeyedFnv;
+///
+/// impl IdDeriver for KeyedFnv {
+///     const TAG: u64 = 0x6b65_7965_645f_666e;
+///
+///     fn derive(bytes: &[u8]) -> u64 {
+///         let mut hash = 0x1234_5678_9abc_def0;
+///         for &byte in bytes {
+///             hash ^= byte as u64;
+///             hash = hash.wrapping_mul(0x100_0000_01b3);
+///         }
+///         hash
+///     }
+/// }
+///
+/// let mut root = Id::<KeyedFnv>::root_with_deriver();
+/// let child = root.next_id();
+/// assert_ne!(child.id(), Id::root().next_id().id());
+/// ```
+pub trait IdDeriver {
+    /// A unique value identifying this deriver.
+    ///
+    /// Roots created with this deriver start from this value, or have it
+    /// folded into their derivation, so even the roots of two different
+    /// derivers are distinct. [`Fnv`] uses 0; pick a
+    /// random value for your own derivers.
+    const TAG: u64;
+
+    /// Hash `bytes` into a new 64-bit ID.
+    fn derive(bytes: &[u8]) -> u64;
+}
+
+/// The default [`IdDeriver`], using the 64-bit FNV-1a hash.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Fnv;
+
+impl IdDeriver for Fnv {
+    const TAG: u64 = 0;
+
+    fn derive(bytes: &[u8]) -> u64 {
+        let mut state = FnvHasher::default();
+        state.write(bytes);
+        state.finish()
+    }
+}
@@ -0,0 +1,447 @@
+use core::fmt;
+
+use crate::ParseIdError;
+
+const HEX: &[u8] = b"0123456789abcdef";
+const CROCKFORD: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const BASE58: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE64_URL: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const BASE64_SORTABLE: &[u8] = b"-0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ_abcdefghijklmnopqrstuvwxyz";
+
+/// The longest encoding: `0x` followed by 32 hex digits.
+const MAX_LEN: usize = 34;
+
+/// A text encoding for final ID's (the output of [`Id::id`](crate::Id::id)).
+///
+/// Every encoding has a minimal form ([`encode`](Encoding::encode)) and a
+/// fixed-width form ([`encode_fixed`](Encoding::encode_fixed)), padded with
+/// leading zero digits. Except for [`Base64Url`](Encoding::Base64Url), the
+/// digits are in ASCII order, so fixed-width strings sort the same way as
+/// the numbers they encode.
+///
+/// ```
+/// use gens::{Encoding, Id};
+///
+/// let mut root = Id::root();
+/// let id = root.next_id().id();
+///
+/// let encoded = Encoding::Base58.encode(id);
+/// assert_eq!(Encoding::Base58.decode(&encoded), Ok(id));
+///
+/// let small = Encoding::Base62.encode_fixed(42);
+/// let large = Encoding::Base62.encode_fixed(1 << 100);
+/// assert_eq!(small.len(), 22);
+/// assert!(small < large);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// Lowercase hexadecimal with a `0x` prefix, the same as `Id`'s
+    /// [`Display`](fmt::Display); up to 34 characters. Decoding also accepts
+    /// uppercase digits.
+    Hex,
+
+    /// [Crockford's base32](https://www.crockford.com/base32.html); up to 26
+    /// characters. Decoding is case-insensitive, and accepts `I` and `L` for
+    /// `1` and `O` for `0`.
+    Base32,
+
+    /// Base58 with the Bitcoin alphabet, which leaves out `0`, `O`, `I` and
+    /// `l`; up to 22 characters.
+    Base58,
+
+    /// Base62 (`0-9`, `A-Z`, `a-z`); up to 22 characters.
+    Base62,
+
+    /// URL-safe base64 (RFC 4648 §5) of the ID's 16 big-endian bytes,
+    /// without padding; always 22 characters, so the minimal and fixed-width
+    /// forms are the same.
+    ///
+    /// This is compatible with other base64 implementations, but its
+    /// alphabet isn't in ASCII order, so the strings *don't* sort like the
+    /// numbers. Use [`Base64Sortable`](Encoding::Base64Sortable) for
+    /// sortable strings of the same length.
+    Base64Url,
+
+    /// Base64 with the URL-safe digits of RFC 4648 §5, reordered to be in
+    /// ASCII order (`-`, `0-9`, `A-Z`, `_`, `a-z`); up to 22 characters.
+    ///
+    /// Since the digits are reordered, this isn't compatible with other
+    /// base64 implementations, but fixed-width strings sort like the
+    /// numbers.
+    Base64Sortable,
+}
+
+impl Encoding {
+    /// Returns the length of the fixed-width form.
+    pub const fn fixed_len(self) -> usize {
+        match self {
+            Encoding::Hex => 34,
+            Encoding::Base32 => 26,
+            Encoding::Base58
+            | Encoding::Base62
+            | Encoding::Base64Url
+            | Encoding::Base64Sortable => 22,
+        }
+    }
+
+    /// Returns the prefix and digits of a positional encoding.
+    fn positional(self) -> Option<(&'static str, &'static [u8])> {
+        match self {
+            Encoding::Hex => Some(("0x", HEX)),
+            Encoding::Base32 => Some(("", CROCKFORD)),
+            Encoding::Base58 => Some(("", BASE58)),
+            Encoding::Base62 => Some(("", BASE62)),
+            Encoding::Base64Sortable => Some(("", BASE64_SORTABLE)),
+            Encoding::Base64Url => None,
+        }
+    }
+
+    /// Encode a final ID without leading zero digits.
+    pub fn encode(self, id: u128) -> EncodedId {
+        self.encode_inner(id, false)
+    }
+
+    /// Encode a final ID padded with leading zero digits to
+    /// [`fixed_len`](Encoding::fixed_len).
+    pub fn encode_fixed(self, id: u128) -> EncodedId {
+        self.encode_inner(id, true)
+    }
+
+    fn encode_inner(self, id: u128, fixed: bool) -> EncodedId {
+        let mut encoded = EncodedId {
+            buf: [0; MAX_LEN],
+            len: self.fixed_len() as u8,
+        };
+
+        let Some((prefix, digits)) = self.positional() else {
+            // The 128 bits are followed by 4 zero bits
+            for (i, byte) in encoded.buf[..22].iter_mut().enumerate() {
+                let shift = 6 * (21 - i as i32) - 4;
+                let value = if shift >= 0 {
+                    id >> shift
+                } else {
+                    id << -shift
+                };
+                *byte = BASE64_URL[value as usize & 63];
+            }
+
+            return encoded;
+        };
+
+        let base = digits.len() as u128;
+        let buf = &mut encoded.buf[prefix.len()..encoded.len as usize];
+        let mut rest = id;
+        let mut used = 0;
+        for byte in buf.iter_mut().rev() {
+            *byte = digits[(rest % base) as usize];
+            rest /= base;
+            used += 1;
+
+            if rest == 0 && !fixed {
+                break;
+            }
+        }
+
+        let start = buf.len() - used;
+        buf.copy_within(start.., 0);
+        buf[used..].fill(0);
+        encoded.buf[..prefix.len()].copy_from_slice(prefix.as_bytes());
+        encoded.len = (prefix.len() + used) as u8;
+        encoded
+    }
+
+    /// Decode a final ID in either the minimal or the fixed-width form.
+    pub fn decode(self, s: &str) -> Result<u128, ParseIdError> {
+        if s.is_empty() {
+            return Err(ParseIdError::InvalidLength);
+        }
+
+        let Some((prefix, digits)) = self.positional() else {
+            return decode_base64_url(s);
+        };
+
+        let s = s
+            .strip_prefix(prefix)
+            .ok_or(ParseIdError::InvalidCharacter)?;
+        if s.is_empty() || prefix.len() + s.len() > self.fixed_len() {
+            return Err(ParseIdError::InvalidLength);
+        }
+
+        let base = digits.len() as u128;
+        s.bytes().try_fold(0u128, |id, byte| {
+            let digit = self.digit(digits, byte)?;
+            id.checked_mul(base)
+                .and_then(|id| id.checked_add(digit))
+                .ok_or(ParseIdError::Overflow)
+        })
+    }
+
+    /// Returns the value of a digit of a positional encoding.
+    fn digit(self, digits: &[u8], byte: u8) -> Result<u128, ParseIdError> {
+        let byte = match self {
+            Encoding::Hex => byte.to_ascii_lowercase(),
+            Encoding::Base32 => match byte.to_ascii_uppercase() {
+                b'I' | b'L' => b'1',
+                b'O' => b'0',
+                byte => byte,
+            },
+            _ => byte,
+        };
+
+        digits
+            .iter()
+            .position(|&digit| digit == byte)
+            .map(|digit| digit as u128)
+            .ok_or(ParseIdError::InvalidCharacter)
+    }
+}
+
+fn decode_base64_url(s: &str) -> Result<u128, ParseIdError> {
+    if s.len() != 22 {
+        return Err(ParseIdError::InvalidLength);
+    }
+
+    let mut id = 0;
+    for (i, byte) in s.bytes().enumerate() {
+        let digit = BASE64_URL
+            .iter()
+            .position(|&digit| digit == byte)
+            .ok_or(ParseIdError::InvalidCharacter)? as u128;
+
+        if i < 21 {
+            id = id << 6 | digit;
+        } else if digit & 0xf != 0 {
+            // Only the high 2 bits of the last character are used
+            return Err(ParseIdError::InvalidCharacter);
+        } else {
+            id = id << 2 | digit >> 4;
+        }
+    }
+
+    Ok(id)
+}
+
+/// Parses a final ID in the format `Id` is [displayed](fmt::Display) in, e.g.
+/// `0x1f`. Shorthand for `Encoding::Hex.decode(s)`.
+///
+/// ```
+/// let mut root = gens::Id::root();
+/// let id = root.next_id();
+///
+/// assert_eq!(gens::parse_final_id(&id.to_string()), Ok(id.id()));
+/// ```
+pub fn parse_final_id(s: &str) -> Result<u128, ParseIdError> {
+    Encoding::Hex.decode(s)
+}
+
+/// A final ID encoded with an [`Encoding`].
+///
+/// Created by [`Encoding::encode`] and [`Encoding::encode_fixed`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodedId {
+    buf: [u8; MAX_LEN],
+    len: u8,
+}
+
+impl EncodedId {
+    /// Returns the encoded string.
+    pub fn as_str(&self) -> &str {
+        // Every encoding is ASCII
+        core::str::from_utf8(&self.buf[..self.len as usize]).unwrap()
+    }
+}
+
+impl core::ops::Deref for EncodedId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for EncodedId {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialOrd for EncodedId {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EncodedId {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl fmt::Debug for EncodedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for EncodedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{parse_final_id, Encoding, Id, ParseIdError};
+
+    const ENCODINGS: [Encoding; 6] = [
+        Encoding::Hex,
+        Encoding::Base32,
+        Encoding::Base58,
+        Encoding::Base62,
+        Encoding::Base64Url,
+        Encoding::Base64Sortable,
+    ];
+
+    fn sample() -> Vec<u128> {
+        let mut root = Id::root_with_seed(15);
+        let mut ids: Vec<_> = (0..1000).map(|_| root.next_id().id()).collect();
+        ids.extend([0, 1, 57, 58, 62, u64::MAX.into(), u128::MAX - 1, u128::MAX]);
+        ids
+    }
+
+    #[test]
+    fn round_trip() {
+        for encoding in ENCODINGS {
+            for id in sample() {
+                let minimal = encoding.encode(id);
+                let fixed = encoding.encode_fixed(id);
+                assert_eq!(fixed.len(), encoding.fixed_len());
+                assert!(minimal.len() <= fixed.len());
+                assert_eq!(encoding.decode(&minimal), Ok(id), "{encoding:?} {minimal}");
+                assert_eq!(encoding.decode(&fixed), Ok(id), "{encoding:?} {fixed}");
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_width_sorts() {
+        let mut ids = sample();
+        ids.sort();
+
+        // RFC 4648's digits aren't in ASCII order
+        for encoding in ENCODINGS.into_iter().filter(|&e| e != Encoding::Base64Url) {
+            let encoded: Vec<_> = ids.iter().map(|&id| encoding.encode_fixed(id)).collect();
+            assert!(encoded.is_sorted(), "{encoding:?}");
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        let id = 0x0123_4567_89ab_cdef_0011_2233_4455_6677;
+        assert_eq!(
+            &*Encoding::Hex.encode(id),
+            "0x123456789abcdef0011223344556677"
+        );
+        assert_eq!(&*Encoding::Hex.encode(0), "0x0");
+        assert_eq!(&*Encoding::Base32.encode(0), "0");
+        assert_eq!(&*Encoding::Base32.encode(31), "Z");
+        assert_eq!(
+            &*Encoding::Base32.encode_fixed(32),
+            "00000000000000000000000010"
+        );
+        assert_eq!(&*Encoding::Base58.encode(0), "1");
+        assert_eq!(&*Encoding::Base58.encode(58), "21");
+        assert_eq!(&*Encoding::Base62.encode(61), "z");
+        assert_eq!(
+            &*Encoding::Base62.encode(u128::MAX),
+            "7n42DGM5Tflk9n8mt7Fhc7"
+        );
+        assert_eq!(&*Encoding::Base64Url.encode(id), "ASNFZ4mrze8AESIzRFVmdw");
+        assert_eq!(
+            &*Encoding::Base64Url.encode(u128::MAX),
+            "_____________________w"
+        );
+        assert_eq!(
+            &*Encoding::Base64Sortable.encode(id),
+            "07oKbXPjCvk-G7YC3KLOr"
+        );
+        assert_eq!(&*Encoding::Base64Sortable.encode(64), "0-");
+        assert_eq!(
+            &*Encoding::Base64Sortable.encode_fixed(0),
+            "----------------------"
+        );
+        assert_eq!(
+            &*Encoding::Base64Sortable.encode(u128::MAX),
+            "2zzzzzzzzzzzzzzzzzzzzz"
+        );
+
+        assert_eq!(format!("{:>4}", Encoding::Base58.encode(58)), "  21");
+        assert_eq!(parse_final_id(&Id::root().to_string()), Ok(Id::root().id()));
+    }
+
+    #[test]
+    fn lenient_decoding() {
+        assert_eq!(Encoding::Hex.decode("0xABcd"), Ok(0xabcd));
+        assert_eq!(Encoding::Base32.decode("1o"), Ok(32));
+        assert_eq!(Encoding::Base32.decode("iL"), Ok(33));
+        assert_eq!(Encoding::Base32.decode("zz"), Ok(1023));
+    }
+
+    #[test]
+    fn invalid() {
+        for encoding in ENCODINGS {
+            assert_eq!(
+                encoding.decode(""),
+                Err(ParseIdError::InvalidLength),
+                "{encoding:?}"
+            );
+        }
+
+        assert_eq!(parse_final_id("0x"), Err(ParseIdError::InvalidLength));
+        assert_eq!(parse_final_id("1f"), Err(ParseIdError::InvalidCharacter));
+        assert_eq!(parse_final_id("0x+1f"), Err(ParseIdError::InvalidCharacter));
+        assert_eq!(parse_final_id("0x1g"), Err(ParseIdError::InvalidCharacter));
+        assert_eq!(
+            parse_final_id(&format!("0x0{:x}", u128::MAX)),
+            Err(ParseIdError::InvalidLength)
+        );
+        assert_eq!(
+            Encoding::Base32.decode("U"),
+            Err(ParseIdError::InvalidCharacter)
+        );
+        assert_eq!(
+            Encoding::Base32.decode("80000000000000000000000000"),
+            Err(ParseIdError::Overflow)
+        );
+        assert_eq!(
+            Encoding::Base58.decode("0"),
+            Err(ParseIdError::InvalidCharacter)
+        );
+        assert_eq!(
+            Encoding::Base62.decode("7n42DGM5Tflk9n8mt7Fhc8"),
+            Err(ParseIdError::Overflow)
+        );
+        assert_eq!(
+            Encoding::Base64Url.decode("AAAA"),
+            Err(ParseIdError::InvalidLength)
+        );
+        assert_eq!(
+            Encoding::Base64Url.decode("AAAAAAAAAAAAAAAAAAAAA+"),
+            Err(ParseIdError::InvalidCharacter)
+        );
+        assert_eq!(
+            Encoding::Base64Url.decode("AAAAAAAAAAAAAAAAAAAAAB"),
+            Err(ParseIdError::InvalidCharacter)
+        );
+        assert_eq!(
+            Encoding::Base64Sortable.decode("AAAA+"),
+            Err(ParseIdError::InvalidCharacter)
+        );
+        assert_eq!(
+            Encoding::Base64Sortable.decode("3---------------------"),
+            Err(ParseIdError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use core::fmt;
+
+/// An error generating a new [`Id`](crate::Id).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GenError {
+    /// The `Id` already generated all of its children: `u32::MAX` of them,
+    /// or `u64::MAX` with [`Overflow::Spill`](crate::Overflow::Spill).
+    ChildrenExhausted,
+
+    /// The `Id` is at the maximum depth (`u32::MAX`), so it can't have
+    /// children.
+    DepthExhausted,
+}
+
+impl fmt::Display for GenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenError::ChildrenExhausted => write!(f, "`Id` has no children left to generate"),
+            GenError::DepthExhausted => write!(f, "`Id` is at the maximum depth"),
+        }
+    }
+}
+
+impl core::error::Error for GenError {}
+
+/// An error decoding an [`Id`](crate::Id) with
+/// [`Id::from_bytes`](crate::Id::from_bytes).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input is too short or too long.
+    InvalidLength,
+
+    /// The input uses an unknown format, e.g. from a newer release.
+    UnknownFormat,
+
+    /// The input uses an unknown [`Version`](crate::Version).
+    UnknownVersion,
+
+    /// The input uses an unknown [`Overflow`](crate::Overflow) behavior.
+    UnknownOverflow,
+
+    /// The input was encoded with a different [`IdDeriver`](crate::IdDeriver).
+    DeriverMismatch,
+
+    /// The input describes an `Id` that can't exist, e.g. a root with a
+    /// parent.
+    Inconsistent,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::InvalidLength => write!(f, "encoded `Id` has an invalid length"),
+            DecodeError::UnknownFormat => write!(f, "encoded `Id` has an unknown format"),
+            DecodeError::UnknownVersion => write!(f, "encoded `Id` has an unknown version"),
+            DecodeError::UnknownOverflow => {
+                write!(f, "encoded `Id` has an unknown overflow behavior")
+            }
+            DecodeError::DeriverMismatch => {
+                write!(f, "encoded `Id` was encoded with a different deriver")
+            }
+            DecodeError::Inconsistent => write!(f, "encoded `Id` is inconsistent"),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// An error parsing a final ID with an [`Encoding`](crate::Encoding).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParseIdError {
+    /// The input is empty, or longer than the encoding's fixed width.
+    InvalidLength,
+
+    /// The input contains a character that isn't part of the encoding, or
+    /// lacks the encoding's prefix.
+    InvalidCharacter,
+
+    /// The input encodes a number that doesn't fit a `u128`.
+    Overflow,
+}
+
+impl fmt::Display for ParseIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseIdError::InvalidLength => write!(f, "encoded ID has an invalid length"),
+            ParseIdError::InvalidCharacter => write!(f, "invalid character in encoded ID"),
+            ParseIdError::Overflow => write!(f, "encoded ID is too large"),
+        }
+    }
+}
+
+impl core::error::Error for ParseIdError {}
+
+/// An error checking an ID against a [`Cluster`](crate::Cluster).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ClusterError {
+    /// The `Id` doesn't belong to any node; see
+    /// [`Id::root_for_node`](crate::Id::root_for_node).
+    NoNode,
+
+    /// The ID claims a node outside the cluster.
+    NodeOutOfRange(u32),
+}
+
+impl fmt::Display for ClusterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClusterError::NoNode => write!(f, "`Id` doesn't belong to a node"),
+            ClusterError::NodeOutOfRange(node) => {
+                write!(f, "node {node} is outside the cluster")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ClusterError {}
+
+/// An error creating a [`SnowflakeLayout`](crate::SnowflakeLayout) or
+/// generating a snowflake with a [`SnowflakeGen`](crate::SnowflakeGen).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SnowflakeError {
+    /// The bit widths don't fit in 63 bits, or the timestamp or sequence
+    /// has none.
+    InvalidLayout,
+
+    /// The `Id` doesn't belong to any node; see
+    /// [`Id::root_for_node`](crate::Id::root_for_node).
+    NoNode,
+
+    /// The `Id`'s node doesn't fit in the layout's node bits.
+    NodeOutOfRange,
+
+    /// The clock is before the layout's epoch, or too far past it.
+    TimestampOutOfRange,
+
+    /// The clock went backwards since the last snowflake.
+    ClockBackwards,
+
+    /// Every sequence number of the current millisecond was used; try again
+    /// in the next one.
+    SequenceExhausted,
+}
+
+impl fmt::Display for SnowflakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnowflakeError::InvalidLayout => write!(f, "invalid snowflake layout"),
+            SnowflakeError::NoNode => write!(f, "`Id` doesn't belong to a node"),
+            SnowflakeError::NodeOutOfRange => write!(f, "node doesn't fit the snowflake layout"),
+            SnowflakeError::TimestampOutOfRange => {
+                write!(f, "timestamp doesn't fit the snowflake layout")
+            }
+            SnowflakeError::ClockBackwards => write!(f, "clock went backwards"),
+            SnowflakeError::SequenceExhausted => {
+                write!(f, "sequence exhausted for the current millisecond")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SnowflakeError {}
@@ -0,0 +1,296 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{parse_final_id, EncodedId, Encoding, Id, ParseIdError};
+
+/// The final ID of an [`Id`], as returned by [`Id::final_id`].
+///
+/// This is a `u128` that can't be mixed up with other integers, and that
+/// [displays](fmt::Display) and parses the same way as an `Id`. It hashes
+/// the same way as both `u128` and `Id`, and implements `Borrow<u128>`, so
+/// e.g. a `HashMap<FinalId, _>` can be queried with either a `FinalId` or a
+/// plain `u128`.
+///
+/// With feature `serde`, it's serialized as a string (see
+/// [`Display`](fmt::Display)) in human-readable formats, and as its 16
+/// [big-endian bytes](FinalId::to_bytes) in binary ones.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use gens::{FinalId, Id};
+///
+/// let mut root = Id::root();
+/// let id = root.next_id();
+///
+/// let mut names = HashMap::new();
+/// names.insert(id.final_id(), "first");
+/// assert_eq!(names.get(&id.final_id()), Some(&"first"));
+/// assert_eq!(names.get(&id.id()), Some(&"first"));
+///
+/// let parsed: FinalId = id.to_string().parse().unwrap();
+/// assert_eq!(parsed, id);
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct FinalId(u128);
+
+impl FinalId {
+    /// Wrap a numerical ID.
+    pub const fn new(id: u128) -> Self {
+        FinalId(id)
+    }
+
+    /// Returns the numerical ID.
+    pub const fn get(self) -> u128 {
+        self.0
+    }
+
+    /// Returns the ID as big-endian bytes, which sort the same way as the
+    /// ID.
+    pub const fn to_bytes(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    /// The reverse of [`FinalId::to_bytes`].
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        FinalId(u128::from_be_bytes(bytes))
+    }
+
+    /// Encode the ID with the given [`Encoding`]. See [`Encoding::encode`].
+    pub fn encode(self, encoding: Encoding) -> EncodedId {
+        encoding.encode(self.0)
+    }
+}
+
+impl<D> Id<D> {
+    /// Returns the numerical ID as a [`FinalId`]. See [`Id::id`].
+    pub fn final_id(&self) -> FinalId {
+        FinalId(self.id())
+    }
+}
+
+impl From<u128> for FinalId {
+    fn from(id: u128) -> Self {
+        FinalId(id)
+    }
+}
+
+impl From<FinalId> for u128 {
+    fn from(id: FinalId) -> Self {
+        id.0
+    }
+}
+
+impl From<[u8; 16]> for FinalId {
+    fn from(bytes: [u8; 16]) -> Self {
+        FinalId::from_bytes(bytes)
+    }
+}
+
+impl From<FinalId> for [u8; 16] {
+    fn from(id: FinalId) -> Self {
+        id.to_bytes()
+    }
+}
+
+impl<D> From<&Id<D>> for FinalId {
+    fn from(id: &Id<D>) -> Self {
+        id.final_id()
+    }
+}
+
+impl<D> From<Id<D>> for FinalId {
+    fn from(id: Id<D>) -> Self {
+        id.final_id()
+    }
+}
+
+impl Borrow<u128> for FinalId {
+    fn borrow(&self) -> &u128 {
+        &self.0
+    }
+}
+
+impl PartialEq<u128> for FinalId {
+    fn eq(&self, other: &u128) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<D> PartialEq<Id<D>> for FinalId {
+    fn eq(&self, other: &Id<D>) -> bool {
+        self.0 == other.id()
+    }
+}
+
+impl<D> PartialEq<FinalId> for Id<D> {
+    fn eq(&self, other: &FinalId) -> bool {
+        self.id() == other.0
+    }
+}
+
+impl FromStr for FinalId {
+    type Err = ParseIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_final_id(s).map(FinalId)
+    }
+}
+
+impl fmt::Debug for FinalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:x}", self.0)
+    }
+}
+
+impl fmt::Display for FinalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:x}", self.0)
+    }
+}
+
+impl fmt::LowerHex for FinalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for FinalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use core::fmt;
+
+    use serde::de::{self, Deserializer, SeqAccess, Visitor};
+    use serde::{Deserialize, Serialize, Serializer};
+
+    use super::FinalId;
+
+    impl Serialize for FinalId {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if serializer.is_human_readable() {
+                serializer.collect_str(self)
+            } else {
+                serializer.serialize_bytes(&self.to_bytes())
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for FinalId {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            if deserializer.is_human_readable() {
+                deserializer.deserialize_str(FinalIdVisitor)
+            } else {
+                deserializer.deserialize_bytes(FinalIdVisitor)
+            }
+        }
+    }
+
+    struct FinalIdVisitor;
+
+    impl<'de> Visitor<'de> for FinalIdVisitor {
+        type Value = FinalId;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a final ID string or 16 bytes")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<FinalId, E> {
+            v.parse().map_err(E::custom)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<FinalId, E> {
+            v.try_into()
+                .map(FinalId::from_bytes)
+                .map_err(|_| E::invalid_length(v.len(), &self))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FinalId, A::Error> {
+            let mut bytes = [0; 16];
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                *byte = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+            }
+
+            if seq.next_element::<u8>()?.is_some() {
+                return Err(de::Error::invalid_length(17, &self));
+            }
+
+            Ok(FinalId::from_bytes(bytes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::hash::BuildHasher;
+
+    use crate::{FinalId, Id, ParseIdError};
+
+    #[test]
+    fn compatible_with_id() {
+        let mut root = Id::root_with_seed(16);
+        let ids: Vec<_> = (0..100).map(|_| root.next_id()).collect();
+
+        let state = std::hash::RandomState::new();
+        let mut map = HashMap::new();
+        for (i, id) in ids.iter().enumerate() {
+            let final_id = id.final_id();
+            assert_eq!(final_id, *id);
+            assert_eq!(*id, final_id);
+            assert_eq!(final_id, id.id());
+            assert_eq!(state.hash_one(final_id), state.hash_one(id));
+            assert_eq!(state.hash_one(final_id), state.hash_one(id.id()));
+
+            map.insert(final_id, i);
+        }
+
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(map.get(&id.final_id()), Some(&i));
+            assert_eq!(map.get(&id.id()), Some(&i));
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let id = FinalId::new(0x0123_4567_89ab_cdef_0011_2233_4455_6677);
+        assert_eq!(id.get(), 0x0123_4567_89ab_cdef_0011_2233_4455_6677);
+        assert_eq!(u128::from(id), id.get());
+        assert_eq!(id.to_bytes()[..4], [0x01, 0x23, 0x45, 0x67]);
+        assert_eq!(FinalId::from(id.to_bytes()), id);
+        assert!(FinalId::new(1).to_bytes() < FinalId::new(256).to_bytes());
+
+        assert_eq!(id.to_string(), "0x123456789abcdef0011223344556677");
+        assert_eq!(format!("{id:?}"), id.to_string());
+        assert_eq!(id.to_string().parse(), Ok(id));
+        assert_eq!("1f".parse::<FinalId>(), Err(ParseIdError::InvalidCharacter));
+
+        let root = Id::root();
+        assert_eq!(FinalId::from(&root).to_string(), root.to_string());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let id = FinalId::new(0x1f);
+
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, r#""0x1f""#);
+        assert_eq!(serde_json::from_str::<FinalId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<FinalId>(r#""1f""#).is_err());
+
+        let binary = bincode::serialize(&id).unwrap();
+        assert_eq!(binary[8..], id.to_bytes());
+        assert_eq!(bincode::deserialize::<FinalId>(&binary).unwrap(), id);
+        assert!(
+            bincode::deserialize::<FinalId>(&bincode::serialize(&[0u8; 15][..]).unwrap()).is_err()
+        );
+    }
+}
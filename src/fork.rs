@@ -0,0 +1,111 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{Id, IdDeriver};
+
+/// How many `Id`s each fork mints in [`Id::par_mint`].
+#[cfg(feature = "rayon")]
+const PAR_MINT_CHUNK: usize = 1024;
+
+impl<D: IdDeriver> Id<D> {
+    /// Fork this `Id` into `N` independent generators, e.g. one per worker.
+    ///
+    /// Each fork is a new child of `self`, so the `Id`s minted by different
+    /// forks live in disjoint subtrees and can never collide with each other,
+    /// nor with the children `self` generates later. No coordination is
+    /// needed between workers, and the result is deterministic.
+    ///
+    /// ```
+    /// use gens::Id;
+    ///
+    /// let mut root = Id::root();
+    /// let [mut a, mut b] = root.fork_array();
+    ///
+    /// assert_ne!(a.next_id(), b.next_id());
+    /// assert_eq!(root.num_children(), 2);
+    /// ```
+    pub fn fork_array<const N: usize>(&mut self) -> [Self; N] {
+        core::array::from_fn(|_| self.next_id())
+    }
+
+    /// Fork this `Id` into `n` independent generators, e.g. one per worker.
+    ///
+    /// See [`Id::fork_array`].
+    #[cfg(feature = "alloc")]
+    pub fn fork(&mut self, n: usize) -> Vec<Self> {
+        (0..n).map(|_| self.next_id()).collect()
+    }
+
+    /// Mint `count` unique `Id`s in parallel using [`rayon`].
+    ///
+    /// This [forks](Id::fork) `self` into a generator for every chunk of
+    /// `Id`s, so the result only depends on `self` and `count`, not on the
+    /// number of threads.
+    #[cfg(feature = "rayon")]
+    pub fn par_mint(&mut self, count: usize) -> Vec<Self> {
+        self.fork(count.div_ceil(PAR_MINT_CHUNK))
+            .into_par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, mut fork)| {
+                let len = PAR_MINT_CHUNK.min(count - i * PAR_MINT_CHUNK);
+                (0..len).map(move |_| fork.next_id())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::Id;
+
+    #[test]
+    fn forks_are_disjoint() {
+        let mut root = Id::root();
+        let forks: [Id; 4] = root.fork_array();
+        assert_eq!(root.num_children(), 4);
+
+        let mut set = HashSet::new();
+        for mut fork in forks {
+            for _ in 0..1000 {
+                assert!(set.insert(fork.next_id().id()));
+            }
+        }
+
+        for _ in 0..1000 {
+            assert!(set.insert(root.next_id().id()));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn fork_matches_fork_array() {
+        let mut a = Id::root();
+        let mut b = Id::root();
+
+        let forks: [Id; 3] = a.fork_array();
+        assert_eq!(b.fork(3), forks);
+        assert_eq!(a.next_id(), b.next_id());
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn par_mint() {
+        let mut a = Id::root();
+        let mut b = Id::root();
+
+        let minted = a.par_mint(5000);
+        assert_eq!(minted.len(), 5000);
+        assert_eq!(minted, b.par_mint(5000));
+        assert_eq!(a.num_children(), 5);
+
+        let set: HashSet<_> = minted.iter().map(Id::id).collect();
+        assert_eq!(set.len(), 5000);
+
+        assert!(a.par_mint(0).is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{FinalId, GenError, Id, IdDeriver};
+
+/// A lightweight, `Copy` handle to an [`Id`]: its identity, without the
+/// ability to generate children.
+///
+/// An `Id` is a generator: copying one would let both copies mint the same
+/// children, so it's neither `Clone` nor `Copy`. A handle only carries the
+/// final ID, the parent's final ID and the depth, so it can be stored freely
+/// in maps and structs. Handles compare, order and hash the same way as the
+/// `Id` they were taken from.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use gens::Id;
+///
+/// let mut root = Id::root();
+/// let (handle, mut child) = root.mint();
+///
+/// let mut owners = HashMap::new();
+/// owners.insert(handle, "alice");
+/// assert_eq!(owners[&child.handle()], "alice");
+///
+/// let (grandchild, _) = child.mint();
+/// assert!(handle.is_parent_of(&grandchild));
+/// assert_eq!(handle, child);
+/// ```
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "HandleState"))]
+pub struct IdHandle {
+    id: u128,
+    parent: u128,
+    depth: u32,
+}
+
+impl IdHandle {
+    /// Returns the numerical ID. See [`Id::id`].
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+
+    /// Returns the numerical ID as a [`FinalId`].
+    pub fn final_id(&self) -> FinalId {
+        FinalId::new(self.id)
+    }
+
+    /// Returns the ID of the parent `Id`; 0 if this is a root ID.
+    pub fn parent(&self) -> u128 {
+        self.parent
+    }
+
+    /// Returns how many ancestors this `Id` has.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Returns whether `self` is the parent of `other`.
+    pub fn is_parent_of(&self, other: &IdHandle) -> bool {
+        other.parent == self.id && other.depth.checked_sub(1) == Some(self.depth)
+    }
+
+    /// Compare two handles by depth alone. See [`Id::cmp_depth`].
+    pub fn cmp_depth(&self, other: &Self) -> Ordering {
+        self.depth.cmp(&other.depth)
+    }
+}
+
+impl<D> Id<D> {
+    /// Returns a `Copy` [`IdHandle`] to this `Id`.
+    pub fn handle(&self) -> IdHandle {
+        IdHandle {
+            id: self.id(),
+            parent: self.parent,
+            depth: self.depth,
+        }
+    }
+}
+
+impl<D: IdDeriver> Id<D> {
+    /// Generate a new, unique `Id` from this one, returning a handle to it
+    /// alongside the child's generator. See [`Id::next_id`].
+    ///
+    /// The handle can be stored and shared freely, while the generator is
+    /// kept wherever more children are minted.
+    pub fn mint(&mut self) -> (IdHandle, Self) {
+        let child = self.next_id();
+        (child.handle(), child)
+    }
+
+    /// Generate a new, unique `Id` from this one, returning a handle to it
+    /// alongside the child's generator. See [`Id::try_next_id`].
+    pub fn try_mint(&mut self) -> Result<(IdHandle, Self), GenError> {
+        let child = self.try_next_id()?;
+        Ok((child.handle(), child))
+    }
+}
+
+impl<D> From<&Id<D>> for IdHandle {
+    fn from(id: &Id<D>) -> Self {
+        id.handle()
+    }
+}
+
+impl From<IdHandle> for FinalId {
+    fn from(handle: IdHandle) -> Self {
+        handle.final_id()
+    }
+}
+
+impl PartialEq for IdHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.depth == other.depth && self.id == other.id
+    }
+}
+
+impl Eq for IdHandle {}
+
+impl<D> PartialEq<Id<D>> for IdHandle {
+    fn eq(&self, other: &Id<D>) -> bool {
+        self.depth == other.depth() && self.id == other.id()
+    }
+}
+
+impl<D> PartialEq<IdHandle> for Id<D> {
+    fn eq(&self, other: &IdHandle) -> bool {
+        other == self
+    }
+}
+
+impl PartialOrd for IdHandle {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Handles are ordered like [`Id`]s: by depth first, then by final ID.
+impl Ord for IdHandle {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_depth(other).then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+impl Hash for IdHandle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl fmt::Debug for IdHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:x}", self.id)
+    }
+}
+
+impl fmt::Display for IdHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:x}", self.id)
+    }
+}
+
+/// The fields of a serialized handle, which are validated before they're
+/// turned into one.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct HandleState {
+    id: u128,
+    parent: u128,
+    depth: u32,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<HandleState> for IdHandle {
+    type Error = crate::DecodeError;
+
+    fn try_from(state: HandleState) -> Result<Self, Self::Error> {
+        // Like an `Id`, a root can't have a parent
+        if state.depth == 0 && state.parent != 0 {
+            return Err(crate::DecodeError::Inconsistent);
+        }
+
+        Ok(IdHandle {
+            id: state.id,
+            parent: state.parent,
+            depth: state.depth,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::hash::BuildHasher;
+
+    use crate::{GenError, Id};
+
+    #[test]
+    fn matches_id() {
+        let mut root = Id::root_with_seed(18);
+        let mut other = Id::root_with_seed(18);
+
+        let state = std::hash::RandomState::new();
+        for _ in 0..100 {
+            let (handle, mut child) = root.mint();
+            let expected = other.next_id();
+
+            assert_eq!(handle, expected);
+            assert_eq!(handle, child.handle());
+            assert_eq!(handle.final_id(), expected.final_id());
+            assert_eq!(handle.parent(), root.id());
+            assert_eq!(handle.depth(), 1);
+            assert_eq!(state.hash_one(handle), state.hash_one(&expected));
+            assert_eq!(handle.to_string(), expected.to_string());
+
+            assert!(root.handle().is_parent_of(&handle));
+            assert!(!handle.is_parent_of(&root.handle()));
+            assert!(handle.is_parent_of(&child.mint().0));
+        }
+    }
+
+    #[test]
+    fn ord_matches_id() {
+        let mut root = Id::root();
+        let mut first = root.next_id();
+        let mut ids: Vec<_> = (0..10).map(|_| root.next_id()).collect();
+        ids.extend((0..10).map(|_| first.next_id()));
+        ids.push(first);
+        ids.push(root);
+
+        let mut handles: Vec<_> = ids.iter().map(Id::handle).collect();
+        ids.sort();
+        handles.sort();
+        assert!(ids.iter().zip(&handles).all(|(id, handle)| id == handle));
+    }
+
+    #[test]
+    fn try_mint() {
+        let mut root = Id::root();
+        let (handle, _) = root.try_mint().unwrap();
+        assert_eq!(handle, Id::root().next_id());
+
+        let mut deepest: Id = Id::from_parts(0, 1, u32::MAX, root.version());
+        assert_eq!(deepest.try_mint().err(), Some(GenError::DepthExhausted));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        use crate::IdHandle;
+
+        let mut root = Id::root_with_seed(18);
+        let handle = root.next_id().handle();
+        let json = serde_json::to_string(&handle).unwrap();
+        assert_eq!(serde_json::from_str::<IdHandle>(&json).unwrap(), handle);
+
+        // A root with a parent
+        let json = r#"{"id":1,"parent":5,"depth":0}"#;
+        assert!(serde_json::from_str::<IdHandle>(json).is_err());
+        assert!(serde_json::from_str::<IdHandle>(r#"{"id":1,"parent":0,"depth":0}"#).is_ok());
+    }
+}
@@ -0,0 +1,324 @@
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{DecodeError, Fnv, GenError, Id, IdDeriver, ENCODED_LEN};
+
+/// The length of [`IdLease::to_bytes`]'s output.
+pub const LEASE_LEN: usize = ENCODED_LEN + 16;
+
+/// A range of consecutive children of an [`Id`], leased with [`Id::lease`].
+///
+/// A lease can generate exactly the children in its range, the same ones the
+/// `Id` would have generated, while the `Id` itself skips the range. Since a
+/// lease is self-contained, it can be sent to another thread, or serialized
+/// and sent to another machine, e.g. by a central service handing out
+/// ranges of ID's.
+///
+/// ```
+/// use gens::{GenError, Id};
+///
+/// let mut root = Id::root();
+/// let mut lease = root.lease(2).unwrap();
+///
+/// let a = lease.next_id().unwrap();
+/// let b = lease.next_id().unwrap();
+/// assert_eq!(lease.next_id().err(), Some(GenError::ChildrenExhausted));
+///
+/// // The parent skipped the leased children
+/// let c = root.next_id();
+/// assert_eq!(root.num_children(), 3);
+/// assert!(a != c && b != c);
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = "D: IdDeriver"))]
+#[cfg_attr(feature = "serde", serde(try_from = "LeaseState<D>"))]
+pub struct IdLease<D = Fnv> {
+    parent: Id<D>,
+    gen: u64,
+    remaining: u64,
+}
+
+impl<D: IdDeriver> Id<D> {
+    /// Lease the next `count` children of this `Id`, which it then skips.
+    /// See [`IdLease`].
+    ///
+    /// Fails if this `Id` doesn't have `count` children left, or is at the
+    /// maximum depth. On error, `self` is left unchanged.
+    pub fn lease(&mut self, count: u64) -> Result<IdLease<D>, GenError> {
+        if self.depth == u32::MAX {
+            return Err(GenError::DepthExhausted);
+        }
+
+        if count > self.overflow.max_children() - self.gen {
+            return Err(GenError::ChildrenExhausted);
+        }
+
+        let lease = IdLease {
+            parent: self.fresh_copy(),
+            gen: self.gen,
+            remaining: count,
+        };
+        self.gen += count;
+        Ok(lease)
+    }
+}
+
+impl<D: IdDeriver> IdLease<D> {
+    /// Generate the next child in the lease, or return an error if the lease
+    /// is exhausted.
+    pub fn next_id(&mut self) -> Result<Id<D>, GenError> {
+        if self.remaining == 0 {
+            return Err(GenError::ChildrenExhausted);
+        }
+
+        self.gen += 1;
+        self.remaining -= 1;
+        let child = self.parent.child_wide(self.gen);
+        Ok(child)
+    }
+
+    /// Split the next `count` children off into a separate lease, e.g. to
+    /// hand them on to another sub-allocator.
+    ///
+    /// Fails if fewer than `count` children are left.
+    pub fn split(&mut self, count: u64) -> Result<Self, GenError> {
+        if count > self.remaining {
+            return Err(GenError::ChildrenExhausted);
+        }
+
+        let lease = IdLease {
+            parent: self.parent.fresh_copy(),
+            gen: self.gen,
+            remaining: count,
+        };
+        self.gen += count;
+        self.remaining -= count;
+        Ok(lease)
+    }
+
+    /// Encode this lease, so it can be sent elsewhere. It's the parent's
+    /// [encoding](Id::to_bytes), followed by the number of children generated
+    /// before the lease's next one, and the number of children left, as 8
+    /// little-endian bytes each.
+    pub fn to_bytes(&self) -> [u8; LEASE_LEN] {
+        let mut bytes = [0; LEASE_LEN];
+        bytes[..ENCODED_LEN].copy_from_slice(&self.parent.to_bytes());
+        bytes[ENCODED_LEN..ENCODED_LEN + 8].copy_from_slice(&self.gen.to_le_bytes());
+        bytes[ENCODED_LEN + 8..].copy_from_slice(&self.remaining.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a lease encoded with [`IdLease::to_bytes`]. See
+    /// [`Id::from_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() != LEASE_LEN {
+            return Err(DecodeError::InvalidLength);
+        }
+
+        let (parent, range) = bytes.split_at(ENCODED_LEN);
+        let (gen, remaining) = range.split_at(8);
+        IdLease::from_parts(
+            Id::from_bytes(parent)?,
+            u64::from_le_bytes(gen.try_into().unwrap()),
+            u64::from_le_bytes(remaining.try_into().unwrap()),
+        )
+    }
+}
+
+impl<D> IdLease<D> {
+    /// Create a lease after checking that its range is valid.
+    fn from_parts(parent: Id<D>, gen: u64, remaining: u64) -> Result<Self, DecodeError> {
+        let max = parent.overflow.max_children();
+        if gen > max || remaining > max - gen {
+            return Err(DecodeError::Inconsistent);
+        }
+
+        Ok(IdLease {
+            parent: parent.fresh_copy(),
+            gen,
+            remaining,
+        })
+    }
+
+    /// Returns the final ID of the `Id` this lease was taken from.
+    pub fn parent(&self) -> u128 {
+        self.parent.id()
+    }
+
+    /// Returns the number of children left in this lease.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns the number of children the parent had generated before this
+    /// lease's next child, i.e. its position among the parent's children.
+    pub fn position(&self) -> u64 {
+        self.gen
+    }
+
+    /// Returns whether this lease has no children left.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl<D> fmt::Debug for IdLease<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdLease")
+            .field("parent", &self.parent)
+            .field("position", &self.gen)
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<D: IdDeriver> Serialize for IdLease<D> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("IdLease", 3)?;
+        state.serialize_field("parent", &Full(&self.parent))?;
+        state.serialize_field("gen", &self.gen)?;
+        state.serialize_field("remaining", &self.remaining)?;
+        state.end()
+    }
+}
+
+/// Serializes a borrowed `Id` with [`crate::serde::full`].
+#[cfg(feature = "serde")]
+struct Full<'a, D>(&'a Id<D>);
+
+#[cfg(feature = "serde")]
+impl<D: IdDeriver> Serialize for Full<'_, D> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::serde::full::serialize(self.0, serializer)
+    }
+}
+
+/// The serialized form of an [`IdLease`], which is validated when
+/// deserialized.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(bound = "D: IdDeriver")]
+struct LeaseState<D> {
+    #[serde(with = "crate::serde::full")]
+    parent: Id<D>,
+    gen: u64,
+    remaining: u64,
+}
+
+#[cfg(feature = "serde")]
+impl<D> TryFrom<LeaseState<D>> for IdLease<D> {
+    type Error = DecodeError;
+
+    fn try_from(state: LeaseState<D>) -> Result<Self, DecodeError> {
+        IdLease::from_parts(state.parent, state.gen, state.remaining)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::{DecodeError, Fnv, GenError, Id, IdLease, Overflow};
+
+    #[test]
+    fn same_children_as_parent() {
+        let mut root = Id::root_with_seed(22);
+        let mut expected = Id::root_with_seed(22);
+
+        root.next_id();
+        let mut lease = root.lease(100).unwrap();
+        assert_eq!(root.total_children(), 101);
+        assert_eq!(lease.position(), 1);
+
+        expected.next_id();
+        for _ in 0..100 {
+            assert_eq!(lease.next_id().unwrap(), expected.next_id());
+        }
+        assert!(lease.is_exhausted());
+        assert_eq!(lease.next_id().err(), Some(GenError::ChildrenExhausted));
+        assert_eq!(root.next_id(), expected.next_id());
+    }
+
+    #[test]
+    fn split() {
+        let mut root = Id::root();
+        let mut lease = root.lease(10).unwrap();
+        let mut sub = lease.split(4).unwrap();
+        assert_eq!(lease.remaining(), 6);
+        assert!(lease.split(7).is_err());
+
+        let mut set = HashSet::new();
+        for _ in 0..4 {
+            assert!(set.insert(sub.next_id().unwrap().id()));
+        }
+        for _ in 0..6 {
+            assert!(set.insert(lease.next_id().unwrap().id()));
+        }
+        assert!(set.insert(root.next_id().id()));
+        assert!(sub.is_exhausted() && lease.is_exhausted());
+    }
+
+    #[test]
+    fn limits() {
+        let mut root = Id::root();
+        root.gen = u32::MAX as u64 - 5;
+        assert_eq!(root.lease(5).err(), None);
+        assert_eq!(root.lease(1).err(), Some(GenError::ChildrenExhausted));
+        assert!(root.lease(0).unwrap().is_exhausted());
+
+        let mut spilling = Id::root().with_overflow(Overflow::Spill);
+        assert_eq!(spilling.lease(u64::MAX).unwrap().remaining(), u64::MAX);
+        assert_eq!(spilling.lease(1).err(), Some(GenError::ChildrenExhausted));
+
+        let mut deepest: Id = Id::from_parts(0, 1, u32::MAX, root.version());
+        assert_eq!(deepest.lease(1).err(), Some(GenError::DepthExhausted));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut root = Id::root();
+        let mut lease = root.lease(5).unwrap();
+        lease.next_id().unwrap();
+
+        let mut decoded = IdLease::from_bytes(&lease.to_bytes()).unwrap();
+        assert_eq!(decoded.remaining(), 4);
+        while !lease.is_exhausted() {
+            assert_eq!(decoded.next_id(), lease.next_id());
+        }
+
+        let decode = |bytes: &[u8]| IdLease::<Fnv>::from_bytes(bytes).map(|l| l.remaining());
+        let mut bytes = lease.to_bytes();
+        assert_eq!(decode(&bytes[1..]), Err(DecodeError::InvalidLength));
+
+        // Past the wrapping limit
+        bytes[crate::ENCODED_LEN + 8] = 1;
+        bytes[crate::ENCODED_LEN..crate::ENCODED_LEN + 8]
+            .copy_from_slice(&(u32::MAX as u64 - 1).to_le_bytes());
+        assert_eq!(decode(&bytes), Ok(1));
+        bytes[crate::ENCODED_LEN + 8] = 2;
+        assert_eq!(decode(&bytes), Err(DecodeError::Inconsistent));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let mut root = Id::root();
+        let lease = root.lease(5).unwrap();
+
+        let json = serde_json::to_string(&lease).unwrap();
+        let decoded: IdLease = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_bytes(), lease.to_bytes());
+
+        let binary = bincode::serialize(&lease).unwrap();
+        let decoded: IdLease = bincode::deserialize(&binary).unwrap();
+        assert_eq!(decoded.to_bytes(), lease.to_bytes());
+
+        let invalid = json.replace(r#""remaining":5"#, r#""remaining":4294967296"#);
+        assert!(serde_json::from_str::<IdLease>(&invalid).is_err());
+    }
+}
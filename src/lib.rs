@@ -1,26 +1,100 @@
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![warn(clippy::all)]
 #![doc = include_str!("../README.md")]
 
+use core::cmp::Ordering;
 use core::fmt;
-use core::hash::{Hash, Hasher};
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::ops::RangeBounds;
 
-use fnv::FnvHasher;
+use path::parse_generations;
 
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use ::serde::{Deserialize, Serialize};
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod by_depth;
+mod bytes;
+mod children;
+mod cluster;
+mod deriver;
+mod encoding;
+mod error;
+mod final_id;
+mod fork;
+mod handle;
+mod lease;
+#[cfg(feature = "alloc")]
+mod lineage;
+mod overflow;
+mod path;
+#[cfg(feature = "std")]
+mod persistent;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "server")]
+pub mod server;
+mod shared;
+mod snapshot;
+mod snowflake;
+#[cfg(feature = "alloc")]
+mod tree;
+mod typed;
+mod version;
+
+pub use by_depth::ByDepth;
+pub use bytes::ENCODED_LEN;
+pub use children::Children;
+pub use cluster::Cluster;
+pub use deriver::{Fnv, IdDeriver};
+pub use encoding::{parse_final_id, EncodedId, Encoding};
+pub use error::{ClusterError, DecodeError, GenError, ParseIdError, SnowflakeError};
+pub use final_id::FinalId;
+pub use handle::IdHandle;
+pub use lease::{IdLease, LEASE_LEN};
+#[cfg(feature = "alloc")]
+pub use lineage::LineageId;
+pub use overflow::Overflow;
+#[cfg(feature = "alloc")]
+pub use path::parse_path;
+pub use path::{display_path, DisplayPath, PathError};
+#[cfg(feature = "std")]
+pub use persistent::{PersistentId, DEFAULT_BLOCK_SIZE};
+pub use shared::SharedId;
+pub use snapshot::{Snapshot, SNAPSHOT_LEN};
+#[cfg(feature = "std")]
+pub use snowflake::SystemClock;
+pub use snowflake::{Clock, Snowflake, SnowflakeGen, SnowflakeLayout};
+#[cfg(feature = "alloc")]
+pub use tree::{Descendants, IdTree, Traversal};
+pub use typed::{TypedFinalId, TypedId};
+pub use version::Version;
 
 /// A unique, 128-bit numerical identifier that can cheaply generate new ID's.
 ///
 /// Retains the parent ID as well as depth information.
 ///
-/// Note: all algorithms are deterministic and platform-independent.
+/// Children are derived using `D`, an [`IdDeriver`]; by default, [`Fnv`].
+///
+/// Note: all algorithms are deterministic and platform-independent, except
+/// for the legacy [`Version::V0`].
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
-pub struct Id {
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+#[cfg_attr(feature = "serde", serde(try_from = "IdState"))]
+pub struct Id<D = Fnv> {
     id: u64,
     parent: u128,
     depth: u32,
-    gen: u32,
+    gen: u64,
+    version: Version,
+    overflow: Overflow,
+    epoch: u64,
+    node: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    deriver: PhantomData<fn() -> D>,
 }
 
 impl Id {
@@ -29,19 +103,338 @@ impl Id {
     /// Note that subsequent calls return *the same ID,* which will generate
     /// *the same children!*
     pub fn root() -> Self {
+        Self::root_with_deriver()
+    }
+
+    /// Create a new root-level `Id` from a seed.
+    ///
+    /// Roots with different seeds produce disjoint trees, so separate
+    /// processes or libraries can each mint their own `Id`s without colliding.
+    /// Identical seeds produce identical roots, just like [`Id::root`].
+    ///
+    /// This is equivalent to `Id::root_in_namespace(&seed.to_le_bytes())`.
+    pub fn root_with_seed(seed: u128) -> Self {
+        Self::root_with_seed_and_deriver(seed)
+    }
+
+    /// Create a new root-level `Id` from an arbitrary namespace, such as the
+    /// name of a service or library.
+    ///
+    /// Roots in different namespaces produce disjoint trees. Identical
+    /// namespaces produce identical roots, just like [`Id::root`].
+    pub fn root_in_namespace(namespace: &[u8]) -> Self {
+        Self::root_in_namespace_with_deriver(namespace)
+    }
+}
+
+impl<D: IdDeriver> Id<D> {
+    /// Create a new root-level `Id` using a custom [`IdDeriver`].
+    ///
+    /// See [`Id::root`].
+    pub fn root_with_deriver() -> Self {
+        Self::from_parts(D::TAG, 0, 0, Version::LATEST)
+    }
+
+    /// Create a new root-level `Id` from a seed, using a custom
+    /// [`IdDeriver`].
+    ///
+    /// See [`Id::root_with_seed`].
+    pub fn root_with_seed_and_deriver(seed: u128) -> Self {
+        Self::root_in_namespace_with_deriver(&seed.to_le_bytes())
+    }
+
+    /// Create a new root-level `Id` from an arbitrary namespace, using a
+    /// custom [`IdDeriver`].
+    ///
+    /// See [`Id::root_in_namespace`].
+    pub fn root_in_namespace_with_deriver(namespace: &[u8]) -> Self {
+        Self::from_parts(Self::derive_root(namespace), 0, 0, Version::LATEST)
+    }
+
+    /// Derive the ID of a root from `input`, folding in the deriver's tag,
+    /// so derivers sharing a hash function still have different roots.
+    ///
+    /// [`Fnv`]'s tag is 0 and isn't folded in, which keeps its roots the same
+    /// as ever.
+    fn derive_root(input: &[u8]) -> u64 {
+        let hash = D::derive(input);
+        if D::TAG == 0 {
+            return hash;
+        }
+
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&D::TAG.to_le_bytes());
+        bytes[8..].copy_from_slice(&hash.to_le_bytes());
+        D::derive(&bytes)
+    }
+
+    /// Generate a new, unique `Id` from this one.
+    ///
+    /// An `Id` can generate up to `u32::MAX` unique children. After that, by
+    /// default, the counter wraps around and the same children are generated
+    /// again; use [`try_next_id`](Id::try_next_id) to detect this instead, or
+    /// opt into [`Overflow::Spill`] to keep generating unique children.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `Id` is at the maximum depth (`u32::MAX`), since its
+    /// children's depth can't be represented.
+    pub fn next_id(&mut self) -> Self {
+        self.gen = self.overflow.normalize(self.gen.wrapping_add(1));
+        self.child_wide(self.gen)
+    }
+
+    /// Generate a new, unique `Id` from this one, or return an error if that's
+    /// impossible.
+    ///
+    /// Unlike [`next_id`](Id::next_id), this never wraps around or panics.
+    /// On error, `self` is left unchanged.
+    pub fn try_next_id(&mut self) -> Result<Self, GenError> {
+        if self.depth == u32::MAX {
+            return Err(GenError::DepthExhausted);
+        }
+
+        if self.gen >= self.overflow.max_children() {
+            return Err(GenError::ChildrenExhausted);
+        }
+
+        self.gen += 1;
+        Ok(self.child_wide(self.gen))
+    }
+
+    /// Returns the child this `Id` generates with the given generation,
+    /// without generating it.
+    ///
+    /// Generations start at 1: `child_at(1)` is the first child
+    /// [`next_id`](Id::next_id) returns, `child_at(2)` the second, and so on.
+    /// This allows recomputing a known child, e.g. after a restart, without
+    /// replaying the whole sequence.
+    ///
+    /// Careful: the returned `Id` generates the same children as the one
+    /// `next_id` returns. Only use one of them to generate children!
+    ///
+    /// ```
+    /// use gens::Id;
+    ///
+    /// let mut root = Id::root();
+    /// root.next_id();
+    /// let second = root.next_id();
+    ///
+    /// assert_eq!(Id::root().child_at(2), second);
+    /// ```
+    pub fn child_at(&self, gen: u32) -> Self {
+        self.child(gen)
+    }
+
+    /// Returns an iterator over the children this `Id` generates with the
+    /// given range of generations, without generating them.
+    ///
+    /// An unbounded start begins at generation 1, the first child. See
+    /// [`Id::child_at`], and heed its warning.
+    ///
+    /// ```
+    /// use gens::Id;
+    ///
+    /// let mut root = Id::root();
+    /// let generated: Vec<_> = (0..5).map(|_| root.next_id()).collect();
+    ///
+    /// let derived: Vec<_> = root.children(..=root.num_children()).collect();
+    /// assert_eq!(derived, generated);
+    /// ```
+    pub fn children<R: RangeBounds<u32>>(&self, gens: R) -> Children<'_, D> {
+        Children::new(self, gens)
+    }
+
+    /// Rebuild the descendant of `root` reached by following `path`, a list
+    /// of generations (see [`Id::child_at`]).
+    ///
+    /// Like the `Id`s [`next_id`](Id::next_id) returns, the result hasn't
+    /// generated any children yet. An empty path returns a copy of `root`.
+    ///
+    /// Careful: the returned `Id` generates the same children as the one it
+    /// was rebuilt from. Only use one of them to generate children!
+    ///
+    /// ```
+    /// use gens::Id;
+    ///
+    /// let mut root = Id::root();
+    /// root.next_id();
+    /// let mut a = root.next_id();
+    /// let b = a.next_id();
+    ///
+    /// assert_eq!(Id::from_path(&Id::root(), &[2, 1]), b);
+    /// assert_eq!(Id::from_path_str(&Id::root(), "root/2/1"), Ok(b));
+    /// ```
+    pub fn from_path(root: &Self, path: &[u32]) -> Self {
+        path.iter()
+            .fold(root.fresh_copy(), |current, &gen| current.child(gen))
+    }
+
+    /// Rebuild the descendant of `root` reached by following a path string
+    /// such as `root/3/17/2`. See [`Id::from_path`], and heed its warning.
+    pub fn from_path_str(root: &Self, path: &str) -> Result<Self, PathError> {
+        let mut current = root.fresh_copy();
+        for gen in parse_generations(path)? {
+            current = current.child(gen?);
+        }
+
+        Ok(current)
+    }
+
+    /// Derive the child of this `Id` with the given generation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `Id` is at the maximum depth.
+    fn child(&self, gen: u32) -> Self {
+        self.child_wide(gen.into())
+    }
+
+    /// Derive the child of this `Id` with the given generation, which may
+    /// have spilled over `u32::MAX`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `Id` is at the maximum depth.
+    fn child_wide(&self, gen: u64) -> Self {
+        let depth = self.depth.checked_add(1).expect("`Id` depth exhausted");
+        let (bytes, len) = self.derivation_input(gen);
+
+        let mut child = Self::from_parts(D::derive(&bytes[..len]), self.id(), depth, self.version)
+            .with_overflow(self.overflow);
+        child.node = self.node;
+        child
+    }
+
+    /// Serialize the state that's hashed to derive the child with the given
+    /// generation, returning the buffer and the length used.
+    fn derivation_input(&self, gen: u64) -> ([u8; 44], usize) {
+        let mut bytes = [0; 44];
+        bytes[..8].copy_from_slice(&self.id.to_le_bytes());
+        bytes[8..24].copy_from_slice(&self.parent.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.depth.to_le_bytes());
+
+        // Generations that spilled over `u32::MAX` are hashed in full, which
+        // leaves the ones below the same as ever
+        let mut len = match u32::try_from(gen) {
+            Ok(gen) => {
+                bytes[28..32].copy_from_slice(&gen.to_le_bytes());
+                32
+            }
+            Err(_) => {
+                bytes[28..36].copy_from_slice(&gen.to_le_bytes());
+                36
+            }
+        };
+
+        // `V0` hashed every field in native byte order
+        if self.version == Version::V0 && cfg!(target_endian = "big") {
+            bytes[..8].reverse();
+            bytes[8..24].reverse();
+            bytes[24..28].reverse();
+            bytes[28..len].reverse();
+        }
+
+        // Restored generators derive different children than the original
+        // (see `Snapshot`), which leaves the others the same as ever
+        if self.epoch != 0 {
+            bytes[len..len + 8].copy_from_slice(&self.epoch.to_le_bytes());
+            len += 8;
+        }
+
+        (bytes, len)
+    }
+}
+
+impl<D> Id<D> {
+    /// Create a fresh `Id` that hasn't produced any children yet.
+    fn from_parts(id: u64, parent: u128, depth: u32, version: Version) -> Self {
         Id {
-            id: 0,
-            parent: 0,
-            depth: 0,
+            id,
+            parent,
+            depth,
             gen: 0,
+            version,
+            overflow: Overflow::Wrap,
+            epoch: 0,
+            node: None,
+            deriver: PhantomData,
         }
     }
 
+    /// Create a copy of this `Id` that hasn't produced any children yet.
+    ///
+    /// Careful: the copy will generate the same children as `self`!
+    fn fresh_copy(&self) -> Self {
+        let mut id = Self::from_parts(self.id, self.parent, self.depth, self.version)
+            .with_overflow(self.overflow);
+        id.epoch = self.epoch;
+        id.node = self.node;
+        id
+    }
+
+    /// Check that this `Id`'s state could actually exist, e.g. after
+    /// decoding it.
+    fn validate(&self) -> Result<(), DecodeError> {
+        let root_with_parent = self.depth == 0 && self.parent != 0;
+        let wrapped = self.gen > self.overflow.max_children();
+
+        if root_with_parent || wrapped {
+            Err(DecodeError::Inconsistent)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Use the given [`Version`] of the derivation algorithm for this `Id`
+    /// and all of its descendants.
+    ///
+    /// New roots use [`Version::LATEST`]. This is meant for freshly created
+    /// roots, e.g. to reproduce a tree created with an older version.
+    pub fn with_version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the [`Version`] of the derivation algorithm this `Id` uses.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Use the given [`Overflow`] behavior for this `Id` and all of its
+    /// descendants, which decides what happens once an `Id` has generated
+    /// `u32::MAX` children.
+    ///
+    /// `Id`s use [`Overflow::Wrap`] by default. Since the children below the
+    /// limit are the same either way, this can be changed at any time.
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self.gen = overflow.normalize(self.gen);
+        self
+    }
+
+    /// Returns the [`Overflow`] behavior this `Id` uses.
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
     /// Returns the numerical ID for use.
     ///
-    /// Returns 0 for the root ID.
+    /// Returns 0 for the ID returned by [`Id::root`]. See also
+    /// [`Id::final_id`], which returns it as a [`FinalId`].
     pub fn id(&self) -> u128 {
-        (self.depth as u128 + 1).wrapping_mul(self.parent ^ (self.id as u128))
+        let id = match self.version {
+            Version::V0 | Version::V1 => {
+                (self.depth as u128 + 1).wrapping_mul(self.parent ^ (self.id as u128))
+            }
+            Version::V2 => mix(self.parent ^ ((self.depth as u128) << 64 | self.id as u128)),
+        };
+
+        // The top bits of a node's ID's are reserved for the node
+        match self.node {
+            Some(node) => (node as u128) << cluster::NODE_SHIFT | id & cluster::HASH_MASK,
+            None => id,
+        }
     }
 
     /// Returns the ID of the parent `Id`; 0 if this is a root ID.
@@ -55,72 +448,483 @@ impl Id {
     }
 
     /// Returns the number of direct children this `Id` has produced.
+    ///
+    /// Saturates at `u32::MAX` if this `Id` spilled over; see
+    /// [`total_children`](Id::total_children).
     pub fn num_children(&self) -> u32 {
-        self.gen
+        self.gen.try_into().unwrap_or(u32::MAX)
     }
 
-    /// Generate a new, unique `Id` from this one.
-    pub fn next_id(&mut self) -> Id {
-        self.gen = self.gen.wrapping_add(1);
-
-        let mut state = FnvHasher::default();
-        self.id.hash(&mut state);
-        self.parent.hash(&mut state);
-        self.depth.hash(&mut state);
-        self.gen.hash(&mut state);
+    /// Returns the number of direct children this `Id` has produced,
+    /// including any that spilled over `u32::MAX`.
+    pub fn total_children(&self) -> u64 {
+        self.gen
+    }
 
-        Id {
-            id: state.finish(),
-            parent: self.id(),
-            depth: self.depth + 1,
-            gen: 0,
-        }
+    /// Compare two `Id`s by depth alone.
+    ///
+    /// Unlike [`Ord`], distinct `Id`s at the same depth compare as
+    /// [`Ordering::Equal`].
+    pub fn cmp_depth(&self, other: &Self) -> Ordering {
+        self.depth.cmp(&other.depth)
     }
 }
 
-impl PartialEq for Id {
+/// A bijective 128-bit mixing function.
+///
+/// Xorshifts and multiplications by odd constants are both invertible, so
+/// distinct inputs always produce distinct outputs. Maps 0 to 0.
+fn mix(mut x: u128) -> u128 {
+    x ^= x >> 64;
+    x = x.wrapping_mul(0x2360ed051fc65da44385df649fccf645);
+    x ^= x >> 64;
+    x = x.wrapping_mul(0x9e3779b97f4a7c15f39cc0605cedc835);
+    x ^ (x >> 64)
+}
+
+impl<D> PartialEq for Id<D> {
     fn eq(&self, other: &Self) -> bool {
-        self.id() == other.id()
+        self.depth == other.depth && self.id() == other.id()
     }
 }
 
-impl Eq for Id {}
+impl<D> Eq for Id<D> {}
 
-impl PartialOrd for Id {
-    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+impl<D> PartialOrd for Id<D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Id {
-    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
-        self.depth.cmp(&other.depth)
+/// `Id`s are ordered by depth first, then by final ID.
+///
+/// To order by depth alone, use [`Id::cmp_depth`] or [`ByDepth`].
+impl<D> Ord for Id<D> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_depth(other)
+            .then_with(|| self.id().cmp(&other.id()))
     }
 }
 
-impl Hash for Id {
+impl<D> Hash for Id<D> {
     fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.id().hash(state)
     }
 }
 
-impl fmt::Debug for Id {
+impl<D> fmt::Debug for Id<D> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "0x{:x}", self.id())
     }
 }
 
-impl fmt::Display for Id {
+impl<D> fmt::Display for Id<D> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "0x{:x}", self.id())
     }
 }
 
+/// The fields of a serialized `Id`, which are validated before they're
+/// turned into one.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct IdState {
+    id: u64,
+    parent: u128,
+    depth: u32,
+    gen: u64,
+    #[serde(default = "Version::legacy")]
+    version: Version,
+    #[serde(default)]
+    overflow: Overflow,
+    #[serde(default)]
+    epoch: u64,
+    #[serde(default)]
+    node: Option<u32>,
+}
+
+#[cfg(feature = "serde")]
+impl<D> TryFrom<IdState> for Id<D> {
+    type Error = DecodeError;
+
+    fn try_from(state: IdState) -> Result<Self, DecodeError> {
+        let mut id = Id::from_parts(state.id, state.parent, state.depth, state.version);
+        id.gen = state.gen;
+        id.overflow = state.overflow;
+        id.epoch = state.epoch;
+        id.node = state.node;
+        id.validate()?;
+        Ok(id)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use std::collections::{HashSet, VecDeque};
+    use std::cmp::Ordering;
+    use std::collections::{BTreeSet, HashSet, VecDeque};
+
+    use crate::{Fnv, GenError, Id, IdDeriver, Overflow, Version};
+
+    /// FNV-1a with a different offset basis.
+    struct OtherFnv;
+
+    impl IdDeriver for OtherFnv {
+        const TAG: u64 = 0x9e37_79b9_7f4a_7c15;
+
+        fn derive(bytes: &[u8]) -> u64 {
+            bytes.iter().fold(0x84222325cbf29ce4, |hash, &byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3)
+            })
+        }
+    }
+
+    /// Mint `count` descendants of `root` breadth-first, returning their ID's.
+    fn descendants<D: IdDeriver>(root: Id<D>, count: usize) -> Vec<u128> {
+        let mut queue = VecDeque::new();
+        let mut ids = Vec::with_capacity(count);
+
+        ids.push(root.id());
+        queue.push_back(root);
+        while ids.len() < count {
+            let mut current = queue.pop_front().unwrap();
+            let next = current.next_id();
+            ids.push(next.id());
 
-    use crate::Id;
+            queue.push_back(next);
+            queue.push_back(current);
+        }
+
+        ids
+    }
+
+    #[test]
+    fn seeded_roots() {
+        assert_eq!(Id::root_with_seed(7), Id::root_with_seed(7));
+        assert_ne!(Id::root_with_seed(7), Id::root_with_seed(8));
+        assert_ne!(Id::root_with_seed(7), Id::root());
+        assert_eq!(
+            Id::root_with_seed(7),
+            Id::root_in_namespace(&7u128.to_le_bytes())
+        );
+
+        assert_eq!(
+            Id::root_in_namespace(b"gens"),
+            Id::root_in_namespace(b"gens")
+        );
+        assert_ne!(
+            Id::root_in_namespace(b"gens"),
+            Id::root_in_namespace(b"snge")
+        );
+    }
+
+    #[test]
+    fn seeded_roots_are_disjoint() {
+        //! Ensure that independent roots don't produce overlapping trees.
+
+        let roots = [
+            Id::root(),
+            Id::root_with_seed(1),
+            Id::root_with_seed(2),
+            Id::root_with_seed(u128::MAX),
+            Id::root_in_namespace(b"service-a"),
+            Id::root_in_namespace(b"service-b"),
+        ];
+
+        let mut seen = HashSet::new();
+        for root in roots {
+            for id in descendants(root, 100_000) {
+                assert!(seen.insert(id), "collision between roots (id={id})");
+            }
+        }
+    }
+
+    #[test]
+    fn derivers_are_disjoint() {
+        //! Ensure that trees built with different derivers don't overlap.
+
+        assert_ne!(Id::root().id(), Id::<OtherFnv>::root_with_deriver().id());
+
+        let mut seen = HashSet::new();
+        for id in descendants(Id::root(), 100_000) {
+            seen.insert(id);
+        }
+
+        for id in descendants(Id::<OtherFnv>::root_with_deriver(), 100_000) {
+            assert!(seen.insert(id), "collision between derivers (id={id})");
+        }
+    }
+
+    #[test]
+    fn deriver_tag_sets_roots_apart() {
+        /// FNV-1a, under another tag.
+        struct TaggedFnv;
+
+        impl IdDeriver for TaggedFnv {
+            const TAG: u64 = 2;
+
+            fn derive(bytes: &[u8]) -> u64 {
+                Fnv::derive(bytes)
+            }
+        }
+
+        assert_ne!(
+            Id::root_with_seed(2).id(),
+            Id::<TaggedFnv>::root_with_seed_and_deriver(2).id()
+        );
+        assert_ne!(
+            Id::root_in_namespace(b"gens").id(),
+            Id::<TaggedFnv>::root_in_namespace_with_deriver(b"gens").id()
+        );
+        assert_ne!(
+            Id::root_for_node(2, 2).id(),
+            Id::<TaggedFnv>::root_for_node_with_deriver(2, 2).id()
+        );
+    }
+
+    /// Final ID's of the children 1-3 of a root, each paired with the
+    /// great-grandchild reached through their first child's second child.
+    type Vectors = [(u128, u128); 3];
+
+    /// Test vectors for [`Version::V2`].
+    const V2: [Vectors; 3] = [
+        [
+            (
+                0xca3ed5876ac91c48049ec0f5450eb9a1,
+                0x767fcf8f80a711a149547614fbe683f1,
+            ),
+            (
+                0x1564c1f831d0d18c78d62a6985b8f15b,
+                0xd181c52bdc59c1ba0ecaca2c2c38bd5e,
+            ),
+            (
+                0x1ce6c9b614a764d744d2d627d417f6db,
+                0xe5e82938160eca53e34ccd7abef87dd2,
+            ),
+        ],
+        [
+            (
+                0x4353ffa185bec78fdaffe636d5269a3f,
+                0xb3034f22b6633cb4c530b59cd9a3bba4,
+            ),
+            (
+                0xf00660a8d948f22f76d2dd097196d3be,
+                0xd7ab884ea0359848815a94caa3e3287a,
+            ),
+            (
+                0xce491ba134098d6fe63ca40e9aa124f6,
+                0xb27faf454aa34fb1310c6f05dbb9e00a,
+            ),
+        ],
+        [
+            (
+                0x98924c8360160483f73646e105e37f34,
+                0xe3ffd56c5472f12cbe80c4187d3fc11e,
+            ),
+            (
+                0xb0050bd2e82df68cdd06da2e87a4d717,
+                0xaf28bb286019467bebb98d7d304b7099,
+            ),
+            (
+                0xd4cc508b773b2b02a77b933ee5f4f3c2,
+                0xe7373c234041d84e3abd6f62a28bf987,
+            ),
+        ],
+    ];
+
+    /// Test vectors for [`Version::V1`], and for [`Version::V0`] on
+    /// little-endian targets.
+    const LITTLE_ENDIAN: [Vectors; 3] = [
+        [
+            (0xd90ec8dfeeaa7228, 0x786f952a0ea2ce258),
+            (0x98eed111f3ecdd8e, 0x93478d57ecd10db80),
+            (0x158f979014781646c, 0x17bb42620fd309b308),
+        ],
+        [
+            (0x3456c02b1b05c49a, 0x2f33d2680d920383c),
+            (0x17458681cceb1427c, 0xe121e888f46f1610c),
+            (0x1b443100c72dafb5e, 0xd2f88d57fbb6db8b8),
+        ],
+        [
+            (0x13bc77311bb5d2216, 0x125604c1724b2aca48),
+            (0x1fbd9d8e0cea999f0, 0xc2e32230a3821caec),
+            (0xbbaca0f0623410d2, 0x6cda7ca136d9a46cc),
+        ],
+    ];
+
+    /// Test vectors for [`Version::V0`] on big-endian targets.
+    const BIG_ENDIAN: [Vectors; 3] = [
+        [
+            (0x19041ef09b15e7e4, 0x1bd47f3ff01c0131c),
+            (0x19041cf09b15e47e, 0x62fd3fb793e1d9e80),
+            (0x19041af09b15e118, 0x191a135014f26b464),
+        ],
+        [
+            (0xc220494e2877c4be, 0x38d11a1e62b307580),
+            (0xc2204f4e2877fecc, 0x49a76369c1f592f5c),
+            (0xc2204d4e2877c272, 0xbafe336acb1ee14c8),
+        ],
+        [
+            (0x161c533d447b17eee, 0x147736330500cf47d0),
+            (0x161c539d447b1703c, 0xc7b541c15354a7f00),
+            (0x161c537d447b175da, 0x13a52ba08e3bad5a64),
+        ],
+    ];
+
+    /// Check the vectors of the root, seeded and namespaced roots.
+    fn check_vectors(version: Version, expected: &[Vectors; 3]) {
+        let roots = [
+            Id::root(),
+            Id::root_with_seed(42),
+            Id::root_in_namespace(b"gens"),
+        ];
+
+        for (root, expected) in roots.into_iter().zip(expected) {
+            let mut root = root.with_version(version);
+            for &(child_id, great_grandchild_id) in expected {
+                let mut child = root.next_id();
+                let mut grandchild = child.next_id();
+                grandchild.next_id();
+                let great_grandchild = grandchild.next_id();
+
+                assert_eq!(child.id(), child_id);
+                assert_eq!(great_grandchild.id(), great_grandchild_id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_vectors() {
+        check_vectors(Version::V2, &V2);
+        check_vectors(Version::V1, &LITTLE_ENDIAN);
+
+        if cfg!(target_endian = "little") {
+            check_vectors(Version::V0, &LITTLE_ENDIAN);
+        } else {
+            check_vectors(Version::V0, &BIG_ENDIAN);
+        }
+    }
+
+    #[test]
+    fn final_id_keeps_high_bits() {
+        //! Ensure that `V2` doesn't lose bits like the legacy final ID does.
+
+        let a = Id::<Fnv>::from_parts(7, 1, 1, Version::V1);
+        let b = Id::<Fnv>::from_parts(7, 1 | 1 << 127, 1, Version::V1);
+        assert_eq!(a.id(), b.id());
+
+        let a = a.with_version(Version::V2);
+        let b = b.with_version(Version::V2);
+        assert_ne!(a.id(), b.id());
+
+        assert_eq!(Id::root().version(), Version::V2);
+        assert_eq!(Id::root().id(), 0);
+    }
+
+    #[test]
+    fn ord_consistent_with_eq() {
+        //! Ensure that `Ord` is a lawful total order that agrees with `Eq`.
+
+        // A few siblings at every depth, plus a second, independent tree
+        let mut ids = Vec::new();
+        for seed in 0..2 {
+            let mut current = Id::root_with_seed(seed);
+            for _ in 0..8 {
+                for _ in 0..6 {
+                    ids.push(current.next_id());
+                }
+                ids.push(current.next_id());
+                current = ids.pop().unwrap();
+            }
+            ids.push(current);
+        }
+
+        for a in &ids {
+            assert_eq!(a.cmp(a), Ordering::Equal);
+
+            for b in &ids {
+                let ord = a.cmp(b);
+                assert_eq!(ord == Ordering::Equal, a == b);
+                assert_eq!(ord.reverse(), b.cmp(a));
+                assert_eq!(Some(ord), a.partial_cmp(b));
+
+                if a.depth() != b.depth() {
+                    assert_eq!(ord, a.cmp_depth(b));
+                }
+
+                for c in &ids {
+                    if a <= b && b <= c {
+                        assert!(a <= c);
+                    }
+                }
+            }
+        }
+
+        // No entries should be silently dropped
+        let len = ids.len();
+        let set: BTreeSet<_> = ids.into_iter().collect();
+        assert_eq!(set.len(), len);
+    }
+
+    #[test]
+    fn checked_generation() {
+        let mut id = Id::root();
+        id.gen = u32::MAX as u64 - 1;
+
+        let last = id.try_next_id().unwrap();
+        assert_eq!(id.num_children(), u32::MAX);
+        assert_eq!(id.try_next_id(), Err(GenError::ChildrenExhausted));
+        assert_eq!(id.num_children(), u32::MAX);
+
+        // `next_id` wraps around, eventually reissuing children
+        assert_ne!(id.next_id(), last);
+        assert_eq!(id.num_children(), 0);
+        assert_eq!(id.next_id(), Id::root().child_at(1));
+
+        let mut deep = Id::<Fnv>::from_parts(1, 2, u32::MAX - 1, Version::LATEST);
+        let mut deepest = deep.try_next_id().unwrap();
+        assert_eq!(deepest.depth(), u32::MAX);
+        assert_eq!(deepest.try_next_id(), Err(GenError::DepthExhausted));
+        assert_eq!(deepest.num_children(), 0);
+    }
+
+    #[test]
+    fn spill_over() {
+        let mut wrapping = Id::root_with_seed(12);
+        let mut spilling = Id::root_with_seed(12).with_overflow(Overflow::Spill);
+
+        // Children below the limit don't change
+        for _ in 0..100 {
+            assert_eq!(spilling.next_id(), wrapping.next_id());
+        }
+
+        spilling.gen = u32::MAX as u64 - 2;
+        let mut set: HashSet<_> = spilling.children(..10).collect();
+        set.extend(spilling.children(u32::MAX - 10..=u32::MAX - 2));
+
+        for _ in 0..10 {
+            let next = spilling.next_id();
+            assert_eq!(next.overflow(), Overflow::Spill);
+            assert!(set.insert(next), "spilled child was reissued");
+        }
+
+        assert_eq!(spilling.num_children(), u32::MAX);
+        assert_eq!(spilling.total_children(), u32::MAX as u64 + 8);
+
+        spilling.gen = u64::MAX;
+        assert_eq!(spilling.try_next_id(), Err(GenError::ChildrenExhausted));
+
+        // Turning spilling off again wraps the counter
+        let mut wrapping = spilling.with_overflow(Overflow::Wrap);
+        assert_eq!(wrapping.num_children(), u32::MAX);
+        assert_eq!(wrapping.next_id(), Id::root_with_seed(12).child_at(0));
+    }
+
+    #[test]
+    #[should_panic = "depth exhausted"]
+    fn depth_overflow_panics() {
+        let mut deepest = Id::<Fnv>::from_parts(1, 2, u32::MAX, Version::LATEST);
+        deepest.next_id();
+    }
 
     #[test]
     #[ignore = "very expensive"]
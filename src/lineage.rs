@@ -0,0 +1,289 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{display_path, DisplayPath, Fnv, GenError, Id, IdDeriver};
+
+/// An [`Id`] that remembers its full ancestry.
+///
+/// A plain `Id` only knows its parent's final ID. A `LineageId` also keeps
+/// the state of the `Id` its lineage starts at (its *origin*), as well as the
+/// sibling index (generation) of every `Id` between the origin and itself.
+/// Since derivation is deterministic, that's enough to recompute every
+/// ancestor exactly, without keeping a registry.
+///
+/// ```
+/// use gens::{Id, LineageId};
+///
+/// let mut root = LineageId::new(Id::root());
+/// let mut a = root.next_id();
+/// let b = a.next_id();
+/// let c = root.next_id();
+///
+/// assert_eq!(b.path(), &[1, 1]);
+/// assert_eq!(b.ancestors().collect::<Vec<_>>(), [a.id(), root.id()]);
+/// assert!(a.is_ancestor_of(&b));
+/// assert_eq!(b.common_ancestor(&c), Some(root.id()));
+/// ```
+pub struct LineageId<D = Fnv> {
+    origin: Id<D>,
+    path: Vec<u32>,
+    id: Id<D>,
+}
+
+impl<D> LineageId<D> {
+    /// Start tracking the lineage of `id` and its descendants, with `id` as
+    /// the origin.
+    pub fn new(id: Id<D>) -> Self {
+        LineageId {
+            origin: id.fresh_copy(),
+            path: Vec::new(),
+            id,
+        }
+    }
+
+    /// Returns the underlying `Id`.
+    pub fn as_id(&self) -> &Id<D> {
+        &self.id
+    }
+
+    /// Discards the lineage, returning the underlying `Id`.
+    pub fn into_id(self) -> Id<D> {
+        self.id
+    }
+
+    /// Returns the numerical ID for use. See [`Id::id`].
+    pub fn id(&self) -> u128 {
+        self.id.id()
+    }
+
+    /// Returns the final ID of the origin of this lineage.
+    pub fn origin(&self) -> u128 {
+        self.origin.id()
+    }
+
+    /// Returns the generation of every `Id` from the origin (exclusive) down
+    /// to this one (inclusive); empty for the origin itself.
+    ///
+    /// The first child of an `Id` has generation 1, the second 2, and so on.
+    pub fn path(&self) -> &[u32] {
+        &self.path
+    }
+
+    /// Returns a [`Display`](fmt::Display)able form of [`path`](Self::path),
+    /// e.g. `root/3/17/2`.
+    pub fn display_path(&self) -> DisplayPath<'_> {
+        display_path(&self.path)
+    }
+
+    /// Returns whether `self` and `other` have the same origin.
+    fn same_origin(&self, other: &Self) -> bool {
+        self.origin == other.origin
+    }
+
+    /// Returns whether `self` is a strict ancestor of `other`.
+    pub fn is_ancestor_of(&self, other: &Self) -> bool {
+        self.same_origin(other)
+            && self.path.len() < other.path.len()
+            && other.path.starts_with(&self.path)
+    }
+}
+
+impl<D: IdDeriver> LineageId<D> {
+    /// Rebuild the `LineageId` reached by following `path` from `root`. See
+    /// [`Id::from_path`], and heed its warning.
+    pub fn from_path(root: &Id<D>, path: &[u32]) -> Self {
+        LineageId {
+            origin: root.fresh_copy(),
+            path: path.to_vec(),
+            id: Id::from_path(root, path),
+        }
+    }
+
+    /// Generate a new, unique `LineageId` from this one. See [`Id::next_id`].
+    ///
+    /// # Panics
+    ///
+    /// Besides the reasons [`Id::next_id`] panics for, this panics if the
+    /// child spilled over `u32::MAX` (see [`Overflow::Spill`]), since its
+    /// generation can't be part of a path.
+    ///
+    /// [`Overflow::Spill`]: crate::Overflow::Spill
+    pub fn next_id(&mut self) -> Self {
+        // Check before generating the child, so the panic doesn't skip it
+        let gen = self.id.overflow.normalize(self.id.gen.wrapping_add(1));
+        let gen = u32::try_from(gen).expect("`LineageId` can't address spilled-over children");
+
+        let id = self.id.next_id();
+        self.with_child(id, gen)
+    }
+
+    /// Generate a new, unique `LineageId` from this one, or return an error
+    /// if that's impossible. See [`Id::try_next_id`].
+    ///
+    /// Since spilled-over children can't be part of a path, a `LineageId`
+    /// runs out of children at `u32::MAX`, even with [`Overflow::Spill`]. On
+    /// error, `self` is left unchanged.
+    ///
+    /// [`Overflow::Spill`]: crate::Overflow::Spill
+    pub fn try_next_id(&mut self) -> Result<Self, GenError> {
+        if self.id.depth == u32::MAX {
+            return Err(GenError::DepthExhausted);
+        }
+
+        if self.id.gen >= u32::MAX as u64 {
+            return Err(GenError::ChildrenExhausted);
+        }
+
+        let id = self.id.try_next_id()?;
+        Ok(self.with_child(id, self.id.gen as u32))
+    }
+
+    /// Returns the `LineageId` of `id`, this one's child with the given
+    /// generation.
+    fn with_child(&self, id: Id<D>, gen: u32) -> Self {
+        let mut path = Vec::with_capacity(self.path.len() + 1);
+        path.extend_from_slice(&self.path);
+        path.push(gen);
+
+        LineageId {
+            origin: self.origin.fresh_copy(),
+            path,
+            id,
+        }
+    }
+
+    /// Returns the final ID of every ancestor of this `Id`, from its parent
+    /// up to the origin of the lineage.
+    pub fn ancestors(&self) -> impl DoubleEndedIterator<Item = u128> + ExactSizeIterator {
+        let mut ids = Vec::with_capacity(self.path.len());
+        let mut current = self.origin.fresh_copy();
+
+        for &gen in self.path.iter() {
+            ids.push(current.id());
+            current = current.child(gen);
+        }
+
+        ids.into_iter().rev()
+    }
+
+    /// Returns the final ID of the deepest `Id` that both `self` and `other`
+    /// descend from (or are); `None` if they have different origins.
+    pub fn common_ancestor(&self, other: &Self) -> Option<u128> {
+        if !self.same_origin(other) {
+            return None;
+        }
+
+        let common = self
+            .path
+            .iter()
+            .zip(other.path.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        Some(Id::from_path(&self.origin, &self.path[..common]).id())
+    }
+}
+
+impl<D> fmt::Debug for LineageId<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LineageId")
+            .field("id", &self.id)
+            .field("origin", &self.origin)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl<D> fmt::Display for LineageId<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.id, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use crate::{GenError, Id, LineageId, Overflow};
+
+    #[test]
+    fn ancestors_match_sequential() {
+        let mut root = Id::root_with_seed(9);
+        root.next_id();
+        let mut a = root.next_id();
+        a.next_id();
+        a.next_id();
+        let mut b = a.next_id();
+        let c = b.next_id();
+
+        let mut lroot = LineageId::new(Id::root_with_seed(9));
+        lroot.next_id();
+        let mut la = lroot.next_id();
+        la.next_id();
+        la.next_id();
+        let mut lb = la.next_id();
+        let lc = lb.next_id();
+
+        assert_eq!(lc.as_id(), &c);
+        assert_eq!(lc.path(), &[2, 3, 1]);
+        assert_eq!(
+            lc.ancestors().collect::<Vec<_>>(),
+            [b.id(), a.id(), root.id()]
+        );
+        assert_eq!(lroot.ancestors().len(), 0);
+
+        let rebuilt = LineageId::from_path(&Id::root_with_seed(9), lc.path());
+        assert_eq!(rebuilt.as_id(), &c);
+        assert_eq!(
+            rebuilt.ancestors().collect::<Vec<_>>(),
+            [b.id(), a.id(), root.id()]
+        );
+        assert_eq!(rebuilt.display_path().to_string(), "root/2/3/1");
+    }
+
+    #[test]
+    fn ancestry() {
+        let mut root = LineageId::new(Id::root());
+        let mut a = root.next_id();
+        let mut b = root.next_id();
+        let mut a1 = a.next_id();
+        let a11 = a1.next_id();
+        let a2 = a.next_id();
+        let b1 = b.next_id();
+
+        assert!(root.is_ancestor_of(&a11));
+        assert!(a.is_ancestor_of(&a11));
+        assert!(a1.is_ancestor_of(&a11));
+        assert!(!a2.is_ancestor_of(&a11));
+        assert!(!a11.is_ancestor_of(&a11));
+        assert!(!a11.is_ancestor_of(&a));
+        assert!(!b.is_ancestor_of(&a11));
+
+        assert_eq!(a11.common_ancestor(&a2), Some(a.id()));
+        assert_eq!(a11.common_ancestor(&b1), Some(root.id()));
+        assert_eq!(a11.common_ancestor(&a1), Some(a1.id()));
+        assert_eq!(a11.common_ancestor(&a11), Some(a11.id()));
+
+        let other = LineageId::new(Id::root_with_seed(1));
+        assert!(!other.is_ancestor_of(&a11));
+        assert_eq!(a11.common_ancestor(&other), None);
+    }
+
+    #[test]
+    fn spilled_children() {
+        let mut root = LineageId::new(Id::root_with_seed(7).with_overflow(Overflow::Spill));
+        root.id.gen = u32::MAX as u64 - 1;
+        let last = root.try_next_id().unwrap();
+        assert_eq!(last.path(), &[u32::MAX]);
+
+        // Neither generator gives up a child it can't address
+        assert_eq!(root.try_next_id().err(), Some(GenError::ChildrenExhausted));
+        assert!(catch_unwind(AssertUnwindSafe(|| root.next_id())).is_err());
+        assert_eq!(root.as_id().total_children(), u32::MAX as u64);
+
+        let mut deepest = LineageId::new(Id::root_with_seed(7));
+        deepest.id.depth = u32::MAX;
+        deepest.id.parent = 1;
+        assert_eq!(deepest.try_next_id().err(), Some(GenError::DepthExhausted));
+    }
+}
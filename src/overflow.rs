@@ -0,0 +1,56 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What an [`Id`](crate::Id) does once it has generated `u32::MAX` children.
+///
+/// Every child inherits its parent's behavior.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[non_exhaustive]
+pub enum Overflow {
+    /// Wrap the child counter around, generating the same children again.
+    #[default]
+    Wrap,
+
+    /// Spill over into a 64-bit child counter, generating unique children
+    /// practically forever.
+    ///
+    /// The children below the limit are the same as with [`Overflow::Wrap`],
+    /// so this can be turned on for existing trees.
+    Spill,
+}
+
+impl Overflow {
+    /// Returns the behavior with the given numerical representation.
+    pub(crate) fn from_repr(repr: u8) -> Option<Overflow> {
+        match repr {
+            0 => Some(Overflow::Wrap),
+            1 => Some(Overflow::Spill),
+            _ => None,
+        }
+    }
+
+    /// Returns the numerical representation of this behavior.
+    pub(crate) fn repr(self) -> u8 {
+        match self {
+            Overflow::Wrap => 0,
+            Overflow::Spill => 1,
+        }
+    }
+
+    /// Returns the maximum number of unique children an `Id` can generate.
+    pub(crate) fn max_children(self) -> u64 {
+        match self {
+            Overflow::Wrap => u32::MAX.into(),
+            Overflow::Spill => u64::MAX,
+        }
+    }
+
+    /// Brings a child counter back into range, wrapping it if necessary.
+    pub(crate) fn normalize(self, gen: u64) -> u64 {
+        match self {
+            Overflow::Wrap => gen as u32 as u64,
+            Overflow::Spill => gen,
+        }
+    }
+}
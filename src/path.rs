@@ -0,0 +1,139 @@
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// An error parsing a path string such as `root/3/17/2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PathError {
+    /// The path doesn't start with `root`.
+    MissingRoot,
+
+    /// A segment of the path isn't a valid generation (a `u32`).
+    InvalidGeneration,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::MissingRoot => write!(f, "path doesn't start with `root`"),
+            PathError::InvalidGeneration => write!(f, "invalid generation in path"),
+        }
+    }
+}
+
+impl core::error::Error for PathError {}
+
+/// Displays a path of generations as e.g. `root/3/17/2`.
+///
+/// Created by [`display_path`].
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayPath<'a>(&'a [u32]);
+
+impl fmt::Display for DisplayPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "root")?;
+        for gen in self.0 {
+            write!(f, "/{gen}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns a [`Display`](fmt::Display)able form of a path of generations,
+/// e.g. `root/3/17/2`. The reverse of [`parse_path`].
+///
+/// ```
+/// assert_eq!(gens::display_path(&[3, 17, 2]).to_string(), "root/3/17/2");
+/// assert_eq!(gens::display_path(&[]).to_string(), "root");
+/// ```
+pub fn display_path(path: &[u32]) -> DisplayPath<'_> {
+    DisplayPath(path)
+}
+
+/// Parses a path string such as `root/3/17/2` into its generations. The
+/// reverse of [`display_path`].
+///
+/// ```
+/// assert_eq!(gens::parse_path("root/3/17/2"), Ok(vec![3, 17, 2]));
+/// assert_eq!(gens::parse_path("root"), Ok(vec![]));
+/// assert!(gens::parse_path("3/17/2").is_err());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn parse_path(path: &str) -> Result<Vec<u32>, PathError> {
+    parse_generations(path)?.collect()
+}
+
+/// Splits a path string into its generations, which are parsed lazily.
+pub(crate) fn parse_generations(
+    path: &str,
+) -> Result<impl Iterator<Item = Result<u32, PathError>> + '_, PathError> {
+    let rest = path.strip_prefix("root").ok_or(PathError::MissingRoot)?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return Err(PathError::MissingRoot);
+    }
+
+    // `rest` is either empty or starts with a separator
+    Ok(rest.split('/').skip(1).map(parse_generation))
+}
+
+fn parse_generation(segment: &str) -> Result<u32, PathError> {
+    // `u32::from_str` accepts a leading `+`
+    if segment.is_empty() || !segment.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(PathError::InvalidGeneration);
+    }
+
+    segment.parse().map_err(|_| PathError::InvalidGeneration)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{display_path, Id, PathError};
+
+    #[test]
+    fn round_trip() {
+        let mut root = Id::root_with_seed(5);
+        let mut current = root.next_id();
+        let mut path = vec![1];
+
+        for i in 0..20 {
+            for _ in 0..i % 4 {
+                current.next_id();
+            }
+
+            let next = current.next_id();
+            path.push(current.num_children());
+
+            assert_eq!(Id::from_path(&root, &path), next);
+
+            let string = display_path(&path).to_string();
+            assert_eq!(Id::from_path_str(&root, &string), Ok(next.fresh_copy()));
+
+            #[cfg(feature = "alloc")]
+            assert_eq!(crate::parse_path(&string).as_ref(), Ok(&path));
+
+            current = next;
+        }
+
+        assert_eq!(Id::from_path(&root, &[]), root);
+        assert_eq!(Id::from_path_str(&root, "root"), Ok(root.fresh_copy()));
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let root = Id::root();
+        let parse = |path| Id::from_path_str(&root, path).map(|id| id.id());
+
+        assert_eq!(parse(""), Err(PathError::MissingRoot));
+        assert_eq!(parse("3/17"), Err(PathError::MissingRoot));
+        assert_eq!(parse("rooted/3"), Err(PathError::MissingRoot));
+        assert_eq!(parse("root/"), Err(PathError::InvalidGeneration));
+        assert_eq!(parse("root//3"), Err(PathError::InvalidGeneration));
+        assert_eq!(parse("root/+3"), Err(PathError::InvalidGeneration));
+        assert_eq!(parse("root/-3"), Err(PathError::InvalidGeneration));
+        assert_eq!(parse("root/4294967296"), Err(PathError::InvalidGeneration));
+        assert!(parse("root/4294967295").is_ok());
+    }
+}
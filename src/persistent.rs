@@ -0,0 +1,429 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::{Fnv, GenError, Id, IdDeriver, IdLease, ENCODED_LEN};
+
+/// How many children [`PersistentId::open`] reserves at once.
+pub const DEFAULT_BLOCK_SIZE: u64 = 1024;
+
+/// The length of a record: an encoded `Id` followed by its checksum.
+const RECORD_LEN: usize = ENCODED_LEN + 8;
+
+/// The error message for a state file without a single intact record.
+const CORRUPT: &str = "corrupt or unknown-format state file";
+
+/// An [`Id`] whose child counter is persisted to a local state file, so it
+/// never generates the same child twice, even across restarts and crashes.
+///
+/// Rather than writing the counter before every child, children are
+/// reserved in blocks: the end of the next block is written and synced to
+/// disk before its first child is generated. After a crash, generation
+/// resumes after the last reserved block, so at most one block of children
+/// is skipped.
+///
+/// The state file holds two checksummed records, which are overwritten
+/// alternately, so a write torn by a crash leaves the previous one intact.
+/// The file is locked while the `PersistentId` exists, so other processes
+/// can't open it at the same time.
+///
+/// ```no_run
+/// use gens::{Id, PersistentId};
+///
+/// let mut gen = PersistentId::open("ids.state", Id::root_in_namespace(b"orders"))?;
+/// let order = gen.next_id()?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct PersistentId<D = Fnv> {
+    id: Id<D>,
+    reserved: u64,
+    block_size: u64,
+    records: u64,
+    file: File,
+}
+
+impl<D: IdDeriver> PersistentId<D> {
+    /// Open the state file at `path`, creating it if it doesn't exist, and
+    /// reserve [`DEFAULT_BLOCK_SIZE`] children.
+    ///
+    /// `id` is the `Id` whose children are generated. If the file already
+    /// exists, it has to belong to the same `Id`, which resumes where the
+    /// file left off. See [`open_with_block_size`].
+    ///
+    /// [`open_with_block_size`]: PersistentId::open_with_block_size
+    pub fn open(path: impl AsRef<Path>, id: Id<D>) -> io::Result<Self> {
+        Self::open_with_block_size(path, id, DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Open the state file at `path`, reserving `block_size` children at
+    /// once. See [`open`](PersistentId::open).
+    ///
+    /// Fails if the file can't be read, is locked by another process (with
+    /// [`io::ErrorKind::WouldBlock`]), is corrupt or in an unknown format, or
+    /// belongs to another `Id` (both with [`io::ErrorKind::InvalidData`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is 0.
+    pub fn open_with_block_size(
+        path: impl AsRef<Path>,
+        mut id: Id<D>,
+        block_size: u64,
+    ) -> io::Result<Self> {
+        assert!(block_size > 0, "block size must be positive");
+
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.try_lock()?;
+
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+
+        let stored = contents
+            .chunks(RECORD_LEN)
+            .enumerate()
+            .filter_map(|(i, record)| Some((i, decode_record::<D>(record)?)))
+            .max_by_key(|(_, stored)| stored.gen);
+
+        // Overwrite the other record first
+        let records = stored.as_ref().map_or(0, |(i, _)| *i as u64 + 1);
+
+        match stored.map(|(_, stored)| stored) {
+            Some(stored)
+                if stored.depth != id.depth
+                    || stored.id() != id.id()
+                    || stored.epoch != id.epoch
+                    || stored.node != id.node =>
+            {
+                return Err(invalid_data("state file belongs to a different `Id`"));
+            }
+            Some(stored) => id.gen = id.gen.max(stored.gen),
+            None if !contents.is_empty() => return Err(invalid_data(CORRUPT)),
+            None => {}
+        }
+
+        let mut persistent = PersistentId {
+            reserved: id.gen,
+            id,
+            block_size,
+            records,
+            file,
+        };
+        persistent.reserve(1)?;
+
+        // The file may have just been created, and its directory entry has to
+        // survive a crash as well as its contents
+        if contents.is_empty() {
+            sync_parent(path)?;
+        }
+
+        Ok(persistent)
+    }
+
+    /// Generate a new, unique `Id`, first reserving another block of
+    /// children if necessary. See [`Id::try_next_id`].
+    ///
+    /// Fails if the reservation can't be written, or if no children are
+    /// left (with [`io::ErrorKind::Other`], wrapping a [`GenError`]).
+    pub fn next_id(&mut self) -> io::Result<Id<D>> {
+        if self.id.gen >= self.reserved {
+            self.reserve(1)?;
+        }
+
+        if self.id.gen >= self.reserved {
+            return Err(io::Error::other(GenError::ChildrenExhausted));
+        }
+
+        self.id.try_next_id().map_err(io::Error::other)
+    }
+
+    /// Lease the next `count` children, first reserving them if necessary.
+    /// See [`Id::lease`].
+    ///
+    /// Fails if the reservation can't be written, or if fewer than `count`
+    /// children are left (with [`io::ErrorKind::Other`], wrapping a
+    /// [`GenError`]).
+    pub fn lease(&mut self, count: u64) -> io::Result<IdLease<D>> {
+        // Check before reserving, so a lease that can't be granted doesn't
+        // use up the children on disk
+        if self.id.depth == u32::MAX {
+            return Err(io::Error::other(GenError::DepthExhausted));
+        }
+
+        if count > self.id.overflow.max_children() - self.id.gen {
+            return Err(io::Error::other(GenError::ChildrenExhausted));
+        }
+
+        if count > self.reserved - self.id.gen {
+            self.reserve(count)?;
+        }
+
+        self.id.lease(count).map_err(io::Error::other)
+    }
+
+    /// Reserve the next block of children, or at least `count` of them, and
+    /// sync the reservation to disk.
+    fn reserve(&mut self, count: u64) -> io::Result<()> {
+        let max = self.id.overflow.max_children();
+        let block = self.block_size.max(count);
+        let reserved = self.id.gen.saturating_add(block).min(max);
+        if reserved == self.reserved {
+            return Ok(());
+        }
+
+        let mut state = self.id.fresh_copy();
+        state.gen = reserved;
+        let bytes = state.to_bytes();
+
+        let mut record = [0; RECORD_LEN];
+        record[..ENCODED_LEN].copy_from_slice(&bytes);
+        record[ENCODED_LEN..].copy_from_slice(&Fnv::derive(&bytes).to_le_bytes());
+
+        // Alternate between the two records, so a torn write can only ever
+        // destroy the older one
+        let offset = (self.records % 2) * RECORD_LEN as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&record)?;
+        self.file.sync_data()?;
+
+        self.records += 1;
+        self.reserved = reserved;
+        Ok(())
+    }
+}
+
+impl<D> PersistentId<D> {
+    /// Returns the underlying `Id`.
+    pub fn as_id(&self) -> &Id<D> {
+        &self.id
+    }
+
+    /// Returns the numerical ID for use. See [`Id::id`].
+    pub fn id(&self) -> u128 {
+        self.id.id()
+    }
+
+    /// Returns the number of children generated so far, including those
+    /// skipped after a crash. See [`Id::total_children`].
+    pub fn total_children(&self) -> u64 {
+        self.id.total_children()
+    }
+
+    /// Returns the number of children reserved on disk.
+    pub fn reserved(&self) -> u64 {
+        self.reserved
+    }
+}
+
+impl<D> core::fmt::Debug for PersistentId<D> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PersistentId")
+            .field("id", &self.id)
+            .field("children", &self.id.gen)
+            .field("reserved", &self.reserved)
+            .finish()
+    }
+}
+
+/// Decode a record, unless it's torn or corrupt.
+fn decode_record<D: IdDeriver>(record: &[u8]) -> Option<Id<D>> {
+    if record.len() != RECORD_LEN {
+        return None;
+    }
+
+    let (bytes, checksum) = record.split_at(ENCODED_LEN);
+    if Fnv::derive(bytes).to_le_bytes() != checksum {
+        return None;
+    }
+
+    Id::from_bytes(bytes).ok()
+}
+
+/// Sync the directory containing `path`, so a new file in it isn't lost.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()
+}
+
+/// Directories can't be synced on other platforms.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{Id, PersistentId};
+
+    use super::RECORD_LEN;
+
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new() -> Self {
+            static COUNT: AtomicUsize = AtomicUsize::new(0);
+            let name = format!(
+                "gens-{}-{}.state",
+                std::process::id(),
+                COUNT.fetch_add(1, Ordering::Relaxed)
+            );
+            TempFile(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn survives_restarts() {
+        let file = TempFile::new();
+        let mut set = HashSet::new();
+
+        for restart in 0..5u64 {
+            let mut gen = PersistentId::open_with_block_size(&file.0, Id::root(), 10).unwrap();
+            // Every restart skips the rest of the previous block
+            assert_eq!(gen.total_children(), restart * 20);
+
+            for _ in 0..15 {
+                assert!(set.insert(gen.next_id().unwrap().id()));
+            }
+            assert_eq!(gen.reserved(), restart * 20 + 20);
+        }
+
+        // The same children as an in-memory `Id`, minus the skipped ones
+        let mut root = Id::root();
+        let expected: HashSet<_> = (0..100).map(|_| root.next_id().id()).collect();
+        assert!(set.is_subset(&expected));
+    }
+
+    #[test]
+    fn locked() {
+        let file = TempFile::new();
+        let _gen = PersistentId::open(&file.0, Id::root()).unwrap();
+
+        let err = PersistentId::open(&file.0, Id::root()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn different_id() {
+        let file = TempFile::new();
+        drop(PersistentId::open(&file.0, Id::root()).unwrap());
+
+        let err = PersistentId::open(&file.0, Id::root_with_seed(21)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn torn_write() {
+        let file = TempFile::new();
+        let mut gen = PersistentId::open_with_block_size(&file.0, Id::root(), 10).unwrap();
+        let mut set = HashSet::new();
+        for _ in 0..10 {
+            set.insert(gen.next_id().unwrap().id());
+        }
+        drop(gen);
+
+        // A crash while reserving the second block tears its record
+        let mut contents = std::fs::read(&file.0).unwrap();
+        assert_eq!(contents.len(), RECORD_LEN);
+        contents.extend_from_slice(&[0xff; RECORD_LEN / 2]);
+        std::fs::write(&file.0, &contents).unwrap();
+
+        // The intact record still prevents reissuing the first block
+        let mut gen = PersistentId::open_with_block_size(&file.0, Id::root(), 10).unwrap();
+        assert_eq!(gen.total_children(), 10);
+        for _ in 0..25 {
+            assert!(set.insert(gen.next_id().unwrap().id()));
+        }
+        drop(gen);
+
+        // Either record may be the newest one
+        let gen = PersistentId::open_with_block_size(&file.0, Id::root(), 10).unwrap();
+        assert_eq!(gen.total_children(), 40);
+        drop(gen);
+
+        std::fs::write(&file.0, [1, 2, 3]).unwrap();
+        let err = PersistentId::open(&file.0, Id::root()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), super::CORRUPT);
+
+        // A file from a newer release
+        std::fs::write(&file.0, [9; RECORD_LEN]).unwrap();
+        let err = PersistentId::open(&file.0, Id::root()).unwrap_err();
+        assert_eq!(err.to_string(), super::CORRUPT);
+    }
+
+    #[test]
+    fn lease() {
+        let file = TempFile::new();
+        let mut gen = PersistentId::open_with_block_size(&file.0, Id::root(), 10).unwrap();
+        gen.next_id().unwrap();
+
+        let mut lease = gen.lease(25).unwrap();
+        assert_eq!(gen.reserved(), 26);
+        assert_eq!(gen.total_children(), 26);
+        drop(gen);
+
+        // The leased children are never generated again
+        let mut root = Id::root();
+        root.next_id();
+        let mut gen = PersistentId::open_with_block_size(&file.0, Id::root(), 10).unwrap();
+        for _ in 0..25 {
+            let child = lease.next_id().unwrap();
+            assert_eq!(child, root.next_id());
+            assert_ne!(child, gen.next_id().unwrap());
+        }
+    }
+
+    #[test]
+    fn rejected_lease() {
+        let file = TempFile::new();
+        let mut gen = PersistentId::open_with_block_size(&file.0, Id::root(), 10).unwrap();
+        assert!(gen.lease(u64::MAX).is_err());
+        assert_eq!(gen.reserved(), 10);
+        drop(gen);
+
+        // The rejected lease didn't reserve anything on disk
+        let mut gen = PersistentId::open_with_block_size(&file.0, Id::root(), 10).unwrap();
+        assert_eq!(gen.total_children(), 10);
+        assert!(gen.next_id().is_ok());
+    }
+
+    #[test]
+    fn exhausted() {
+        let file = TempFile::new();
+        let mut root = Id::root();
+        root.gen = u32::MAX as u64 - 2;
+
+        let mut gen = PersistentId::open(&file.0, root).unwrap();
+        assert_eq!(gen.reserved(), u32::MAX as u64);
+        assert!(gen.lease(3).is_err());
+        assert!(gen.next_id().is_ok());
+        assert!(gen.next_id().is_ok());
+        assert!(gen.next_id().is_err());
+    }
+}
@@ -0,0 +1,513 @@
+//! Alternative [`serde`] representations of [`Id`], for use with
+//! `#[serde(with = "...")]`.
+//!
+//! `Id` itself serializes as a struct of its internal fields. Instead,
+//! these modules offer:
+//!
+//! - [`full`]: the full state as a single value: a hex string in
+//!   human-readable formats, the bytes of [`Id::to_bytes`] in binary ones.
+//! - [`compact`]: the full state as a tuple, with the parent's final ID
+//!   serialized like a [`FinalId`].
+//! - [`final_hex`]: only the final ID, as a string such as `0x1f`. Since an
+//!   `Id` can't be recovered from its final ID, this deserializes into
+//!   final ID's only.
+//! - [`legacy`]: deserializes `Id`s serialized by the first release, whose
+//!   derived representation had fewer fields.
+//!
+//! When deserializing, `full` and `compact` reject states that an `Id` can't
+//! be in, such as a root with a parent.
+//!
+//! ```
+//! use gens::Id;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct User {
+//!     #[serde(with = "gens::serde::full")]
+//!     id: Id,
+//!     #[serde(with = "gens::serde::final_hex")]
+//!     team: u128,
+//! }
+//!
+//! let mut root = Id::root();
+//! let team = root.next_id().id();
+//! let user = User { id: root.next_id(), team };
+//!
+//! let json = serde_json::to_string(&user).unwrap();
+//! assert!(json.contains(&format!(r#""team":"{team:#x}""#)));
+//!
+//! let decoded: User = serde_json::from_str(&json).unwrap();
+//! assert_eq!(decoded.id, user.id);
+//! ```
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use ::serde::de::{self, Deserializer, SeqAccess, Visitor};
+use ::serde::ser::{SerializeTuple, Serializer};
+use ::serde::Deserialize;
+
+use crate::{
+    DecodeError, FinalId, Id, IdDeriver, IdHandle, Overflow, TypedFinalId, TypedId, Version,
+};
+
+/// Types that have a final ID, which [`final_hex`] can serialize.
+pub trait HasFinalId {
+    /// Returns the final ID.
+    fn final_id(&self) -> FinalId;
+}
+
+impl<D> HasFinalId for Id<D> {
+    fn final_id(&self) -> FinalId {
+        Id::final_id(self)
+    }
+}
+
+impl<T, D> HasFinalId for TypedId<T, D> {
+    fn final_id(&self) -> FinalId {
+        self.final_id().get()
+    }
+}
+
+impl HasFinalId for IdHandle {
+    fn final_id(&self) -> FinalId {
+        IdHandle::final_id(self)
+    }
+}
+
+impl HasFinalId for FinalId {
+    fn final_id(&self) -> FinalId {
+        *self
+    }
+}
+
+impl<T> HasFinalId for TypedFinalId<T> {
+    fn final_id(&self) -> FinalId {
+        self.get()
+    }
+}
+
+impl HasFinalId for u128 {
+    fn final_id(&self) -> FinalId {
+        FinalId::new(*self)
+    }
+}
+
+fn invalid<E: de::Error>(error: DecodeError) -> E {
+    E::custom(error)
+}
+
+/// The full state of an `Id`, as a hex string in human-readable formats and
+/// as the bytes of [`Id::to_bytes`] in binary ones.
+pub mod full {
+    use super::*;
+
+    /// Serialize an `Id`; see [the module documentation](self).
+    pub fn serialize<D: IdDeriver, S: Serializer>(
+        id: &Id<D>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let bytes = id.to_bytes();
+        if serializer.is_human_readable() {
+            serializer.collect_str(&Hex(&bytes))
+        } else {
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+
+    /// Deserialize an `Id`; see [the module documentation](self).
+    pub fn deserialize<'de, D: IdDeriver, De: Deserializer<'de>>(
+        deserializer: De,
+    ) -> Result<Id<D>, De::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(FullVisitor(PhantomData))
+        } else {
+            deserializer.deserialize_bytes(FullVisitor(PhantomData))
+        }
+    }
+
+    struct Hex<'a>(&'a [u8]);
+
+    impl fmt::Display for Hex<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+        }
+    }
+
+    struct FullVisitor<D>(PhantomData<fn() -> D>);
+
+    impl<D: IdDeriver> Visitor<'_> for FullVisitor<D> {
+        type Value = Id<D>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "an encoded `Id`")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Id<D>, E> {
+            let mut bytes = [0; crate::ENCODED_LEN];
+            if !v.len().is_multiple_of(2) || v.len() > 2 * bytes.len() {
+                return Err(invalid(DecodeError::InvalidLength));
+            }
+
+            let bytes = &mut bytes[..v.len() / 2];
+            for (byte, digits) in bytes.iter_mut().zip(v.as_bytes().chunks(2)) {
+                let digits = core::str::from_utf8(digits).map_err(E::custom)?;
+                *byte = u8::from_str_radix(digits, 16)
+                    .ok()
+                    .filter(|_| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))?;
+            }
+
+            self.visit_bytes(bytes)
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Id<D>, E> {
+            Id::from_bytes(v).map_err(invalid)
+        }
+    }
+}
+
+/// The full state of an `Id` as a tuple of its version, overflow behavior,
+/// ID, parent's final ID, depth, number of children, epoch (see
+/// [`Snapshot`](crate::Snapshot)) and optional node (see
+/// [`Id::root_for_node`]).
+///
+/// Unlike [`full`], this doesn't record the [`IdDeriver`], so it can't tell
+/// `Id`s with different derivers apart.
+pub mod compact {
+    use super::*;
+
+    /// Serialize an `Id`; see [the module documentation](self).
+    pub fn serialize<D, S: Serializer>(id: &Id<D>, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(8)?;
+        tuple.serialize_element(&(id.version as u8))?;
+        tuple.serialize_element(&id.overflow.repr())?;
+        tuple.serialize_element(&id.id)?;
+        tuple.serialize_element(&FinalId::new(id.parent))?;
+        tuple.serialize_element(&id.depth)?;
+        tuple.serialize_element(&id.gen)?;
+        tuple.serialize_element(&id.epoch)?;
+        tuple.serialize_element(&id.node)?;
+        tuple.end()
+    }
+
+    /// Deserialize an `Id`; see [the module documentation](self).
+    pub fn deserialize<'de, D, De: Deserializer<'de>>(
+        deserializer: De,
+    ) -> Result<Id<D>, De::Error> {
+        deserializer.deserialize_tuple(8, CompactVisitor(PhantomData))
+    }
+
+    struct CompactVisitor<D>(PhantomData<fn() -> D>);
+
+    impl<'de, D> Visitor<'de> for CompactVisitor<D> {
+        type Value = Id<D>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a tuple of 8 elements")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Id<D>, A::Error> {
+            let version: u8 = element(&mut seq, 0, &self)?;
+            let overflow: u8 = element(&mut seq, 1, &self)?;
+            let id: u64 = element(&mut seq, 2, &self)?;
+            let parent: FinalId = element(&mut seq, 3, &self)?;
+            let depth: u32 = element(&mut seq, 4, &self)?;
+            let gen: u64 = element(&mut seq, 5, &self)?;
+            let epoch: u64 = element(&mut seq, 6, &self)?;
+            let node: Option<u32> = element(&mut seq, 7, &self)?;
+
+            let version =
+                Version::from_repr(version).ok_or_else(|| invalid(DecodeError::UnknownVersion))?;
+            let overflow = Overflow::from_repr(overflow)
+                .ok_or_else(|| invalid(DecodeError::UnknownOverflow))?;
+
+            let mut id = Id::from_parts(id, parent.get(), depth, version);
+            id.overflow = overflow;
+            id.gen = gen;
+            id.epoch = epoch;
+            id.node = node;
+            id.validate().map_err(invalid)?;
+            Ok(id)
+        }
+    }
+
+    fn element<'de, A: SeqAccess<'de>, T: Deserialize<'de>>(
+        seq: &mut A,
+        i: usize,
+        expected: &dyn de::Expected,
+    ) -> Result<T, A::Error> {
+        seq.next_element()?
+            .ok_or_else(|| de::Error::invalid_length(i, expected))
+    }
+}
+
+/// Only the final ID, as a string such as `0x1f` in every format.
+///
+/// This serializes anything that [has a final ID](HasFinalId), e.g. an
+/// [`Id`], [`IdHandle`], [`FinalId`] or `u128`, but since an `Id` can't be
+/// recovered from its final ID, it only deserializes into final ID's, e.g.
+/// [`FinalId`] or `u128`. To serialize an `Id` this way, use
+/// `#[serde(serialize_with = "gens::serde::final_hex::serialize")]`.
+pub mod final_hex {
+    use super::*;
+
+    /// Serialize a final ID; see [the module documentation](self).
+    pub fn serialize<T: HasFinalId + ?Sized, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&value.final_id())
+    }
+
+    /// Deserialize a final ID; see [the module documentation](self).
+    pub fn deserialize<'de, T: From<FinalId>, De: Deserializer<'de>>(
+        deserializer: De,
+    ) -> Result<T, De::Error> {
+        deserializer.deserialize_str(FinalHexVisitor).map(T::from)
+    }
+
+    struct FinalHexVisitor;
+
+    impl Visitor<'_> for FinalHexVisitor {
+        type Value = FinalId;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a final ID string")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<FinalId, E> {
+            v.parse().map_err(E::custom)
+        }
+    }
+}
+
+/// Deserialize an `Id` serialized by the first release, as a struct of its
+/// ID, parent, depth and a 32-bit number of children, e.g. to migrate data
+/// stored with bincode or postcard.
+///
+/// `Id`'s own representation has gained fields since, so formats that
+/// aren't self-describing can't deserialize old `Id`s with it. Use
+/// `#[serde(deserialize_with = "gens::serde::legacy::deserialize")]` to read
+/// them, then store them again in the current representation.
+///
+/// ```
+/// use gens::Id;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct OldUser {
+///     #[serde(deserialize_with = "gens::serde::legacy::deserialize")]
+///     id: Id,
+/// }
+/// ```
+pub mod legacy {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct LegacyId {
+        id: u64,
+        parent: u128,
+        depth: u32,
+        gen: u32,
+    }
+
+    /// Deserialize an `Id`; see [the module documentation](self).
+    pub fn deserialize<'de, D, De: Deserializer<'de>>(
+        deserializer: De,
+    ) -> Result<Id<D>, De::Error> {
+        let legacy = LegacyId::deserialize(deserializer)?;
+
+        // The first release always used the first version
+        let mut id = Id::from_parts(legacy.id, legacy.parent, legacy.depth, Version::V0);
+        id.gen = legacy.gen.into();
+        id.validate().map_err(invalid)?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::serde::{Deserialize, Serialize};
+
+    use crate::{FinalId, Id, IdHandle, Overflow, Version};
+
+    #[derive(Serialize, Deserialize)]
+    struct Full(#[serde(with = "crate::serde::full")] Id);
+
+    #[derive(Serialize, Deserialize)]
+    struct Compact(#[serde(with = "crate::serde::compact")] Id);
+
+    #[derive(Serialize, Deserialize)]
+    struct FinalHex {
+        #[serde(with = "crate::serde::final_hex")]
+        final_id: FinalId,
+        #[serde(with = "crate::serde::final_hex")]
+        raw: u128,
+    }
+
+    #[derive(Serialize)]
+    struct Minted {
+        #[serde(serialize_with = "crate::serde::final_hex::serialize")]
+        id: Id,
+        #[serde(with = "crate::serde::final_hex")]
+        handle: IdHandle,
+    }
+
+    fn samples() -> Vec<Id> {
+        let mut root = Id::root_with_seed(19);
+        let mut child = root.next_id();
+        child.next_id();
+        let mut spilling = Id::root().with_overflow(Overflow::Spill);
+        spilling.gen = u64::MAX;
+        let node = Id::root_for_node(19, 19).next_id();
+        vec![root, child, spilling, node]
+    }
+
+    #[test]
+    fn full() {
+        for id in samples() {
+            let value = Full(id);
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(json.len(), 2 + 2 * crate::ENCODED_LEN);
+            let decoded: Full = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded.0.to_bytes(), value.0.to_bytes());
+
+            let binary = bincode::serialize(&value).unwrap();
+            assert_eq!(binary[8..], value.0.to_bytes());
+            let decoded: Full = bincode::deserialize(&binary).unwrap();
+            assert_eq!(decoded.0.to_bytes(), value.0.to_bytes());
+        }
+    }
+
+    #[test]
+    fn compact() {
+        for id in samples() {
+            let value = Compact(id);
+            let json = serde_json::to_string(&value).unwrap();
+            let decoded: Compact = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded.0.to_bytes(), value.0.to_bytes());
+
+            let binary = bincode::serialize(&value).unwrap();
+            let decoded: Compact = bincode::deserialize(&binary).unwrap();
+            assert_eq!(decoded.0.to_bytes(), value.0.to_bytes());
+        }
+
+        let mut root = Id::root();
+        let json = serde_json::to_string(&Compact(root.next_id())).unwrap();
+        assert_eq!(json, r#"[2,0,7820329710005860628,"0x0",1,0,0,null]"#);
+    }
+
+    #[test]
+    fn final_hex() {
+        let mut root = Id::root();
+        let id = root.next_id();
+        let hex = id.to_string();
+
+        let value = FinalHex {
+            final_id: id.final_id(),
+            raw: id.id(),
+        };
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, format!(r#"{{"final_id":"{hex}","raw":"{hex}"}}"#));
+
+        let decoded: FinalHex = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.final_id, id);
+        assert_eq!(decoded.raw, id.id());
+
+        let binary = bincode::serialize(&value).unwrap();
+        let decoded: FinalHex = bincode::deserialize(&binary).unwrap();
+        assert_eq!(decoded.final_id, id);
+        assert!(serde_json::from_str::<FinalHex>(r#"{"final_id":"1","raw":"0x1"}"#).is_err());
+
+        let minted = Minted {
+            handle: id.handle(),
+            id,
+        };
+        let json = serde_json::to_string(&minted).unwrap();
+        assert_eq!(json, format!(r#"{{"id":"{hex}","handle":"{hex}"}}"#));
+    }
+
+    #[test]
+    fn legacy() {
+        /// `Id`'s derived representation in the first release.
+        #[derive(Serialize)]
+        struct BaselineId {
+            id: u64,
+            parent: u128,
+            depth: u32,
+            gen: u32,
+        }
+
+        #[derive(Deserialize)]
+        struct Legacy(#[serde(deserialize_with = "crate::serde::legacy::deserialize")] Id);
+
+        let old = BaselineId {
+            id: 7,
+            parent: 3,
+            depth: 1,
+            gen: 2,
+        };
+        let binary = bincode::serialize(&old).unwrap();
+        let json = serde_json::to_string(&old).unwrap();
+
+        for decoded in [
+            bincode::deserialize::<Legacy>(&binary).unwrap().0,
+            serde_json::from_str::<Legacy>(&json).unwrap().0,
+        ] {
+            assert_eq!(decoded.version(), Version::V0);
+            assert_eq!(decoded.depth(), 1);
+            assert_eq!(decoded.num_children(), 2);
+            assert_eq!(decoded.to_bytes()[11..35], binary[..24]);
+        }
+
+        // The current representation only reads old `Id`s from
+        // self-describing formats
+        assert!(bincode::deserialize::<Id>(&binary).is_err());
+        let current: Id = serde_json::from_str(&json).unwrap();
+        let legacy = serde_json::from_str::<Legacy>(&json).unwrap().0;
+        assert_eq!(current.to_bytes(), legacy.to_bytes());
+
+        let root = BaselineId {
+            id: 7,
+            parent: 3,
+            depth: 0,
+            gen: 0,
+        };
+        let binary = bincode::serialize(&root).unwrap();
+        assert!(bincode::deserialize::<Legacy>(&binary).is_err());
+    }
+
+    #[test]
+    fn rejects_inconsistent() {
+        // A root with a parent
+        assert!(serde_json::from_str::<Compact>(r#"[2,0,1,"0x1",0,0,0,null]"#).is_err());
+        // A wrapping `Id` past `u32::MAX` children
+        assert!(serde_json::from_str::<Compact>(r#"[2,0,1,"0x1",1,4294967296,0,null]"#).is_err());
+        assert!(serde_json::from_str::<Compact>(r#"[2,1,1,"0x1",1,4294967296,0,null]"#).is_ok());
+        // Unknown version and overflow behavior
+        assert!(serde_json::from_str::<Compact>(r#"[9,0,1,"0x1",1,0,0,null]"#).is_err());
+        assert!(serde_json::from_str::<Compact>(r#"[2,9,1,"0x1",1,0,0,null]"#).is_err());
+        assert!(serde_json::from_str::<Compact>(r#"[2,0,1,"0x1",1,0,0]"#).is_err());
+
+        // The derived representation is validated as well
+        let json = serde_json::to_string(&Id::root_with_seed(19).next_id()).unwrap();
+        assert!(serde_json::from_str::<Id>(&json).is_ok());
+        assert!(serde_json::from_str::<Id>(r#"{"id":1,"parent":0,"depth":0,"gen":0}"#).is_ok());
+        let invalid = [
+            r#"{"id":1,"parent":5,"depth":0,"gen":0}"#,
+            r#"{"id":1,"parent":5,"depth":1,"gen":99999999999}"#,
+        ];
+        for json in invalid {
+            assert!(serde_json::from_str::<Id>(json).is_err());
+        }
+
+        let mut bytes = Id::root_with_seed(1).next_id().to_bytes();
+        bytes[35] = 0;
+        let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert!(serde_json::from_str::<Full>(&format!(r#""{hex}""#)).is_err());
+        assert!(bincode::deserialize::<Full>(&bincode::serialize(&bytes[..]).unwrap()).is_err());
+        assert!(serde_json::from_str::<Full>(r#""0102""#).is_err());
+        assert!(serde_json::from_str::<Full>(&format!(r#""{}""#, "+1".repeat(55))).is_err());
+    }
+}
@@ -0,0 +1,333 @@
+//! A small ID allocation daemon and its client.
+//!
+//! A [`Server`] owns a [`PersistentId`] and serves its children to
+//! [`Client`]s, either one at a time or as [`IdLease`]s, over TCP or a Unix
+//! socket. The `gens-server` binary runs one from the command line.
+//!
+//! # Protocol
+//!
+//! Every message is a frame: its length as 4 little-endian bytes, followed
+//! by that many bytes of payload, at most [`MAX_FRAME_LEN`]. A client sends
+//! requests and the server answers each one in order, on the same
+//! connection. Requests start with a byte saying what's requested:
+//!
+//! - `0`: a single child, answered with its [encoding](Id::to_bytes).
+//! - `1`, followed by a count as 8 little-endian bytes: a lease of that many
+//!   children, answered with its [encoding](IdLease::to_bytes).
+//!
+//! Responses start with a status byte: `0` if the request succeeded,
+//! followed by the answer, or `1` if it failed, followed by an error message
+//! in UTF-8.
+//!
+//! ```no_run
+//! use std::net::{TcpListener, TcpStream};
+//! use gens::server::{Client, Server};
+//! use gens::{Id, PersistentId};
+//!
+//! // On the server
+//! let server = Server::new(PersistentId::open("ids.state", Id::root())?);
+//! let listener = TcpListener::bind("127.0.0.1:7870")?;
+//! std::thread::spawn(move || server.serve_tcp(&listener));
+//!
+//! // On a client
+//! let mut client = Client::new(TcpStream::connect("127.0.0.1:7870")?);
+//! let id = client.next_id()?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use crate::{Fnv, Id, IdDeriver, IdLease, PersistentId, ENCODED_LEN, LEASE_LEN};
+
+/// The maximum length of a frame's payload.
+pub const MAX_FRAME_LEN: usize = 1024;
+
+/// How many children [`Client::next_id`] leases at once.
+pub const DEFAULT_BATCH: u64 = 64;
+
+/// How long to wait after failing to accept a connection.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(10);
+
+const REQUEST_ID: u8 = 0;
+const REQUEST_LEASE: u8 = 1;
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+
+/// Serves the children of a [`PersistentId`] to [`Client`]s.
+///
+/// Every connection is served on its own thread, and all of them share the
+/// same `PersistentId`, so every child is handed out once.
+pub struct Server<D = Fnv> {
+    id: Mutex<PersistentId<D>>,
+}
+
+impl<D: IdDeriver> Server<D> {
+    /// Create a server handing out the children of `id`.
+    pub fn new(id: PersistentId<D>) -> Self {
+        Server { id: Mutex::new(id) }
+    }
+
+    /// Accept connections on `listener` forever, serving each one on a new
+    /// thread.
+    ///
+    /// Failing to accept a connection, e.g. because the process ran out of
+    /// file descriptors or the client gave up, doesn't stop the server; it
+    /// waits for a moment and tries again.
+    pub fn serve_tcp(&self, listener: &TcpListener) -> ! {
+        self.serve(|| listener.accept().map(|(stream, _)| stream))
+    }
+
+    /// Accept connections on `listener` forever, serving each one on a new
+    /// thread. See [`Server::serve_tcp`].
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: &UnixListener) -> ! {
+        self.serve(|| listener.accept().map(|(stream, _)| stream))
+    }
+
+    /// Accept connections with `accept` forever, serving each one on a new
+    /// thread.
+    fn serve<S>(&self, accept: impl Fn() -> io::Result<S>) -> !
+    where
+        S: Read + Write + Send,
+    {
+        thread::scope(|scope| loop {
+            match accept() {
+                Ok(stream) => {
+                    scope.spawn(|| self.serve_connection(stream));
+                }
+                // Most failures are transient, and the others shouldn't
+                // turn into a busy loop
+                Err(_) => thread::sleep(ACCEPT_BACKOFF),
+            }
+        })
+    }
+
+    /// Answer requests on a single connection until the client closes it.
+    ///
+    /// Fails if the connection fails, or the client sends a malformed frame
+    /// (with [`io::ErrorKind::InvalidData`]). Invalid requests within a
+    /// frame are answered with an error instead.
+    pub fn serve_connection<S: Read + Write>(&self, mut stream: S) -> io::Result<()> {
+        while let Some(request) = read_frame(&mut stream, true)? {
+            let response = match self.answer(&request) {
+                Ok(answer) => [&[STATUS_OK], &answer[..]].concat(),
+                Err(e) => [&[STATUS_ERROR], e.to_string().as_bytes()].concat(),
+            };
+            write_frame(&mut stream, &response)?;
+        }
+
+        Ok(())
+    }
+
+    /// Answer a single request.
+    fn answer(&self, request: &[u8]) -> io::Result<Vec<u8>> {
+        let mut id = self.id.lock().unwrap_or_else(PoisonError::into_inner);
+        match request {
+            [REQUEST_ID] => Ok(id.next_id()?.to_bytes().to_vec()),
+            [REQUEST_LEASE, count @ ..] if count.len() == 8 => {
+                let count = u64::from_le_bytes(count.try_into().unwrap());
+                Ok(id.lease(count)?.to_bytes().to_vec())
+            }
+            _ => Err(invalid_data("invalid request")),
+        }
+    }
+}
+
+impl<D> core::fmt::Debug for Server<D> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Server").field("id", &self.id).finish()
+    }
+}
+
+/// A connection to a [`Server`], which caches leased children.
+///
+/// [`Client::next_id`] leases a batch of children at once, then hands them
+/// out locally until the lease is exhausted. Children left over when the
+/// client is dropped are never handed out again.
+pub struct Client<S, D = Fnv> {
+    stream: S,
+    batch: u64,
+    lease: Option<IdLease<D>>,
+}
+
+impl<S: Read + Write> Client<S> {
+    /// Create a client for the server at the other end of `stream`, leasing
+    /// [`DEFAULT_BATCH`] children at once.
+    pub fn new(stream: S) -> Self {
+        Self::with_deriver(stream)
+    }
+}
+
+impl<S: Read + Write, D: IdDeriver> Client<S, D> {
+    /// Create a client for a server using a custom [`IdDeriver`]. See
+    /// [`Client::new`].
+    pub fn with_deriver(stream: S) -> Self {
+        Client {
+            stream,
+            batch: DEFAULT_BATCH,
+            lease: None,
+        }
+    }
+
+    /// Lease `batch` children at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch` is 0.
+    pub fn with_batch(mut self, batch: u64) -> Self {
+        assert!(batch > 0, "batch size must be positive");
+        self.batch = batch;
+        self
+    }
+
+    /// Return the next cached child, leasing a new batch from the server
+    /// once the cached one is exhausted.
+    ///
+    /// Fails if the server can't be reached, or can't hand out more
+    /// children (with [`io::ErrorKind::Other`]).
+    pub fn next_id(&mut self) -> io::Result<Id<D>> {
+        if let Some(id) = self.lease.as_mut().and_then(|lease| lease.next_id().ok()) {
+            return Ok(id);
+        }
+
+        let mut lease = self.lease(self.batch)?;
+        let id = lease.next_id().map_err(io::Error::other)?;
+        self.lease = Some(lease);
+        Ok(id)
+    }
+
+    /// Request a single child directly from the server, bypassing the
+    /// cache. See [`Client::next_id`].
+    pub fn fetch_id(&mut self) -> io::Result<Id<D>> {
+        let answer = self.request(&[REQUEST_ID], ENCODED_LEN)?;
+        Id::from_bytes(&answer).map_err(invalid_data)
+    }
+
+    /// Request a lease of `count` children from the server, bypassing the
+    /// cache. See [`Client::next_id`].
+    pub fn lease(&mut self, count: u64) -> io::Result<IdLease<D>> {
+        let mut request = [REQUEST_LEASE; 9];
+        request[1..].copy_from_slice(&count.to_le_bytes());
+
+        let answer = self.request(&request, LEASE_LEN)?;
+        IdLease::from_bytes(&answer).map_err(invalid_data)
+    }
+
+    /// Send a request, and return the answer if it's `len` bytes long.
+    fn request(&mut self, request: &[u8], len: usize) -> io::Result<Vec<u8>> {
+        write_frame(&mut self.stream, request)?;
+        let response = read_frame(&mut self.stream, false)?.unwrap();
+
+        match response.split_first() {
+            Some((&STATUS_OK, answer)) if answer.len() == len => Ok(answer.to_vec()),
+            Some((&STATUS_ERROR, message)) => Err(io::Error::other(
+                String::from_utf8_lossy(message).into_owned(),
+            )),
+            _ => Err(invalid_data("invalid response")),
+        }
+    }
+}
+
+impl<S, D> Client<S, D> {
+    /// Returns the number of cached children left.
+    pub fn cached(&self) -> u64 {
+        self.lease.as_ref().map_or(0, IdLease::remaining)
+    }
+}
+
+impl<S, D> core::fmt::Debug for Client<S, D> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Client")
+            .field("batch", &self.batch)
+            .field("lease", &self.lease)
+            .finish()
+    }
+}
+
+/// Read a frame, or `None` if the stream ends before it, and `eof` is
+/// allowed.
+fn read_frame(stream: &mut impl Read, eof: bool) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len) {
+        Err(e) if eof && e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        result => result?,
+    }
+
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(invalid_data("frame is too long"));
+    }
+
+    let mut payload = vec![0; len];
+    stream.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+fn write_frame(stream: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len()).unwrap();
+    stream.write_all(&[&len.to_le_bytes(), payload].concat())?;
+    stream.flush()
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+    use std::net::{TcpListener, TcpStream};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use crate::{Id, PersistentId};
+
+    use super::{Client, Server};
+
+    /// A temporary file path, removed when dropped.
+    struct TempPath(PathBuf);
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn survives_failed_accepts() {
+        let path = TempPath(
+            std::env::temp_dir().join(format!("gens-accept-{}.state", std::process::id())),
+        );
+        let id = PersistentId::open(&path.0, Id::root_with_seed(23)).unwrap();
+        let server = Server::new(id);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        // Every other accept fails, e.g. because file descriptors ran out
+        let failures = AtomicUsize::new(0);
+        thread::spawn(move || {
+            server.serve(|| {
+                if failures.fetch_add(1, Ordering::Relaxed).is_multiple_of(2) {
+                    return Err(io::Error::other("too many open files"));
+                }
+                listener.accept().map(|(stream, _)| stream)
+            })
+        });
+
+        for _ in 0..3 {
+            let mut client = Client::new(TcpStream::connect(address).unwrap());
+            assert!(client.fetch_id().is_ok());
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use core::fmt;
+
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(all(not(loom), target_has_atomic = "64"))]
+use core::sync::atomic::{AtomicU64, Ordering};
+
+#[cfg(all(not(loom), not(target_has_atomic = "64")))]
+use portable_atomic::{AtomicU64, Ordering};
+
+use crate::{Fnv, GenError, Id, IdDeriver};
+
+/// An [`Id`] that can generate children through a shared reference.
+///
+/// The child counter is atomic, so many threads can call
+/// [`next_id`](SharedId::next_id) concurrently (e.g. through an `Arc`) and
+/// still receive unique children, without a `Mutex`. The children are exactly
+/// the ones the wrapped `Id` would have generated, though the order they're
+/// handed out in depends on the threads.
+///
+/// The wrapped `Id`'s [`Overflow`](crate::Overflow) behavior is respected.
+/// On targets without 64-bit atomic compare-and-swap, this falls back to
+/// [`portable-atomic`](https://crates.io/crates/portable-atomic).
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use gens::{Id, SharedId};
+///
+/// let root = Arc::new(SharedId::new(Id::root()));
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let root = Arc::clone(&root);
+///         thread::spawn(move || root.next_id())
+///     })
+///     .collect();
+///
+/// let children: Vec<Id> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+/// assert_eq!(root.num_children(), 4);
+/// assert!(children.iter().all(|child| child.parent() == root.id()));
+/// ```
+pub struct SharedId<D = Fnv> {
+    inner: Id<D>,
+    gen: AtomicU64,
+}
+
+impl<D> SharedId<D> {
+    /// Wrap an `Id`, continuing from its current number of children.
+    pub fn new(id: Id<D>) -> Self {
+        let gen = AtomicU64::new(id.gen);
+        SharedId { inner: id, gen }
+    }
+
+    /// Unwrap the `Id`, keeping every child generated so far.
+    pub fn into_inner(self) -> Id<D> {
+        let mut id = self.inner;
+        id.gen = id.overflow.normalize(self.gen.load(Ordering::Relaxed));
+        id
+    }
+
+    /// Returns the numerical ID for use. See [`Id::id`].
+    pub fn id(&self) -> u128 {
+        self.inner.id()
+    }
+
+    /// Returns the ID of the parent `Id`; 0 if this is a root ID.
+    pub fn parent(&self) -> u128 {
+        self.inner.parent()
+    }
+
+    /// Returns how many ancestors this `Id` has.
+    pub fn depth(&self) -> u32 {
+        self.inner.depth()
+    }
+
+    /// Returns the number of direct children this `Id` has produced. See
+    /// [`Id::num_children`].
+    pub fn num_children(&self) -> u32 {
+        self.total_children().try_into().unwrap_or(u32::MAX)
+    }
+
+    /// Returns the number of direct children this `Id` has produced,
+    /// including any that spilled over `u32::MAX`.
+    pub fn total_children(&self) -> u64 {
+        self.inner
+            .overflow
+            .normalize(self.gen.load(Ordering::Relaxed))
+    }
+}
+
+impl<D: IdDeriver> SharedId<D> {
+    /// Generate a new, unique `Id` from this one. See [`Id::next_id`].
+    pub fn next_id(&self) -> Id<D> {
+        // Every read-modify-write on `gen` sees a distinct value, no matter
+        // the ordering, and nothing else is synchronized through it. With
+        // `Overflow::Wrap`, the counter itself runs freely and is only
+        // wrapped when used.
+        let gen = self.gen.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+        self.inner.child_wide(self.inner.overflow.normalize(gen))
+    }
+
+    /// Generate a new, unique `Id` from this one, or return an error if that's
+    /// impossible. See [`Id::try_next_id`].
+    pub fn try_next_id(&self) -> Result<Id<D>, GenError> {
+        if self.inner.depth == u32::MAX {
+            return Err(GenError::DepthExhausted);
+        }
+
+        let overflow = self.inner.overflow;
+        let gen = self
+            .gen
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |gen| {
+                let gen = overflow.normalize(gen);
+                (gen < overflow.max_children()).then_some(gen + 1)
+            })
+            .map_err(|_| GenError::ChildrenExhausted)?;
+
+        Ok(self.inner.child_wide(overflow.normalize(gen) + 1))
+    }
+}
+
+impl<D> From<Id<D>> for SharedId<D> {
+    fn from(id: Id<D>) -> Self {
+        Self::new(id)
+    }
+}
+
+impl<D> fmt::Debug for SharedId<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+impl<D> fmt::Display for SharedId<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::{GenError, Id, Overflow, SharedId};
+
+    #[test]
+    fn matches_sequential() {
+        let shared = SharedId::new(Id::root());
+        let mut root = Id::root();
+
+        for _ in 0..1000 {
+            assert_eq!(shared.next_id(), root.next_id());
+        }
+
+        assert_eq!(shared.into_inner().num_children(), 1000);
+    }
+
+    #[test]
+    fn checked_generation() {
+        let mut id = Id::root();
+        id.gen = u32::MAX as u64 - 1;
+        let shared = SharedId::new(id);
+
+        assert_eq!(shared.try_next_id(), Ok(Id::root().child_at(u32::MAX)));
+        assert_eq!(shared.try_next_id(), Err(GenError::ChildrenExhausted));
+        assert_eq!(shared.num_children(), u32::MAX);
+
+        // Wrapping around makes room again
+        assert_eq!(shared.next_id(), Id::root().child_at(0));
+        assert_eq!(shared.try_next_id(), Ok(Id::root().child_at(1)));
+        assert_eq!(shared.into_inner().num_children(), 1);
+    }
+
+    #[test]
+    fn spill_over() {
+        let mut id = Id::root().with_overflow(Overflow::Spill);
+        id.gen = u32::MAX as u64;
+        let shared = SharedId::new(id);
+
+        let mut id = Id::root().with_overflow(Overflow::Spill);
+        id.gen = u32::MAX as u64;
+
+        for _ in 0..10 {
+            assert_eq!(shared.next_id(), id.next_id());
+            assert_eq!(shared.try_next_id(), id.try_next_id());
+        }
+
+        assert_eq!(shared.num_children(), u32::MAX);
+        assert_eq!(shared.total_children(), u32::MAX as u64 + 20);
+    }
+
+    #[test]
+    fn concurrent_children_are_unique() {
+        let shared = Arc::new(SharedId::new(Id::root_with_seed(3)));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    (0..10_000)
+                        .map(|_| shared.next_id().id())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut set = HashSet::new();
+        for handle in handles {
+            for id in handle.join().unwrap() {
+                assert!(set.insert(id), "duplicate child (id={id})");
+            }
+        }
+
+        assert_eq!(shared.num_children(), 80_000);
+    }
+}
@@ -0,0 +1,228 @@
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{DecodeError, Fnv, Id, IdDeriver, ENCODED_LEN};
+
+/// The length of [`Snapshot::to_bytes`]'s output.
+pub const SNAPSHOT_LEN: usize = ENCODED_LEN + 8;
+
+/// A stored copy of an [`Id`]'s state, which can be restored safely.
+///
+/// Resuming a plain copy of an `Id` (e.g. one decoded with
+/// [`Id::from_bytes`]) generates the same children as the original, so
+/// restoring it twice, or restoring it after the original generated more
+/// children, silently creates duplicates. A `Snapshot` counts how often it
+/// was restored, and every restored `Id` is given a new *epoch*, which is
+/// folded into the derivation of its children. Restored `Id`s therefore
+/// never generate the children of the original, nor those of each other.
+///
+/// Every restore is made by a *replica*, such as a process or a machine,
+/// identified by a `u64` of the caller's choosing. The replica is folded
+/// into the epoch, so replicas restoring copies of the same stored snapshot
+/// (e.g. from a shared backup) still get disjoint epochs, as long as their
+/// replica ID's differ. A single replica has to store the snapshot again
+/// after every [`restore`](Snapshot::restore), before the restored `Id` is
+/// used.
+///
+/// The restored `Id` keeps its identity, e.g. its final ID and its number of
+/// children, but all of its children, including those derived with
+/// [`Id::child_at`] and [`Id::from_path`], belong to the new epoch.
+///
+/// ```
+/// use gens::{Id, Snapshot};
+///
+/// let mut root = Id::root();
+/// let mut snapshot = root.snapshot();
+/// let original = root.next_id();
+///
+/// // E.g. after a crash, the stored snapshot is restored and stored again
+/// let stored = snapshot.to_bytes();
+/// let mut restored = snapshot.restore(1);
+/// let restored_stored = snapshot.to_bytes();
+///
+/// assert_eq!(restored, root);
+/// assert_ne!(restored.next_id(), original);
+///
+/// let mut snapshot: Snapshot = Snapshot::from_bytes(&restored_stored).unwrap();
+/// assert_eq!(snapshot.restores(), 1);
+///
+/// // Another replica restores the same stored snapshot
+/// let mut copy: Snapshot = Snapshot::from_bytes(&stored).unwrap();
+/// assert_ne!(copy.restore(2).next_id(), restored.next_id());
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(bound = "D: IdDeriver"))]
+pub struct Snapshot<D = Fnv> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::full"))]
+    id: Id<D>,
+    restores: u64,
+}
+
+impl<D: IdDeriver> Id<D> {
+    /// Take a [`Snapshot`] of this `Id`'s current state.
+    pub fn snapshot(&self) -> Snapshot<D> {
+        let mut id = self.fresh_copy();
+        id.gen = self.gen;
+
+        Snapshot { id, restores: 0 }
+    }
+}
+
+impl<D: IdDeriver> Snapshot<D> {
+    /// Restore the `Id` in a new epoch on behalf of `replica`, counting the
+    /// restore.
+    ///
+    /// Different replicas must use different `replica` ID's, and the same
+    /// replica must store the snapshot again before using the returned `Id`!
+    pub fn restore(&mut self, replica: u64) -> Id<D> {
+        self.restores += 1;
+
+        let mut input = [0; 32];
+        input[..8].copy_from_slice(&self.id.epoch.to_le_bytes());
+        input[8..16].copy_from_slice(&self.id.gen.to_le_bytes());
+        input[16..24].copy_from_slice(&self.restores.to_le_bytes());
+        input[24..].copy_from_slice(&replica.to_le_bytes());
+
+        let mut id = self.id.fresh_copy();
+        id.gen = self.id.gen;
+        // Epoch 0 is reserved for `Id`s that were never restored
+        id.epoch = D::derive(&input).max(1);
+        id
+    }
+
+    /// Returns how often this snapshot was restored.
+    pub fn restores(&self) -> u64 {
+        self.restores
+    }
+
+    /// Encode this snapshot, so it can be stored. It's the `Id`'s
+    /// [encoding](Id::to_bytes), followed by the number of restores as 8
+    /// little-endian bytes.
+    pub fn to_bytes(&self) -> [u8; SNAPSHOT_LEN] {
+        let mut bytes = [0; SNAPSHOT_LEN];
+        bytes[..ENCODED_LEN].copy_from_slice(&self.id.to_bytes());
+        bytes[ENCODED_LEN..].copy_from_slice(&self.restores.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a snapshot encoded with [`Snapshot::to_bytes`]. See
+    /// [`Id::from_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() != SNAPSHOT_LEN {
+            return Err(DecodeError::InvalidLength);
+        }
+
+        let (id, restores) = bytes.split_at(ENCODED_LEN);
+        Ok(Snapshot {
+            id: Id::from_bytes(id)?,
+            restores: u64::from_le_bytes(restores.try_into().unwrap()),
+        })
+    }
+}
+
+impl<D> fmt::Debug for Snapshot<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("id", &self.id)
+            .field("children", &self.id.gen)
+            .field("restores", &self.restores)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::{DecodeError, Id, Snapshot};
+
+    fn mint(id: &mut Id, set: &mut HashSet<u128>) {
+        for _ in 0..1000 {
+            let mut child = id.next_id();
+            assert!(set.insert(child.id()));
+            assert!(set.insert(child.next_id().id()));
+        }
+    }
+
+    #[test]
+    fn restores_are_disjoint() {
+        let mut root = Id::root_with_seed(20);
+        root.next_id();
+        let mut snapshot = root.snapshot();
+
+        let mut set = HashSet::new();
+        mint(&mut root, &mut set);
+
+        let mut stale = root.snapshot();
+        for _ in 0..3 {
+            let mut restored = snapshot.restore(0);
+            assert_eq!(restored, root);
+            assert_eq!(restored.num_children(), 1);
+            mint(&mut restored, &mut set);
+
+            let mut restored = stale.restore(0);
+            assert_eq!(restored.num_children(), 1001);
+            mint(&mut restored, &mut set);
+        }
+
+        // Restoring a restored `Id` works the same way
+        let mut restored = snapshot.restore(0);
+        mint(&mut restored, &mut set);
+        let mut nested = restored.snapshot();
+        for _ in 0..3 {
+            mint(&mut nested.restore(0), &mut set);
+        }
+    }
+
+    #[test]
+    fn replicas_are_disjoint() {
+        let mut root = Id::root_with_seed(20);
+        root.next_id();
+        let stored = root.snapshot().to_bytes();
+
+        // Every replica restores its own copy of the same stored snapshot
+        let mut set = HashSet::new();
+        mint(&mut root, &mut set);
+        for replica in 0..3 {
+            let mut snapshot: Snapshot = Snapshot::from_bytes(&stored).unwrap();
+            mint(&mut snapshot.restore(replica), &mut set);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut root = Id::root();
+        root.next_id();
+        let mut snapshot = root.snapshot();
+        snapshot.restore(0);
+
+        let mut decoded = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+        assert_eq!(decoded.restores(), 1);
+        assert_eq!(decoded.to_bytes(), snapshot.to_bytes());
+        assert_eq!(decoded.restore(0).next_id(), snapshot.restore(0).next_id());
+
+        // The epoch of a restored `Id` survives encoding
+        let mut restored = snapshot.restore(0);
+        let mut decoded = Id::from_bytes(&restored.to_bytes()).unwrap();
+        assert_eq!(decoded.next_id(), restored.next_id());
+
+        assert_eq!(
+            Snapshot::<crate::Fnv>::from_bytes(&[0; 10]).map(|s| s.restores()),
+            Err(DecodeError::InvalidLength)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let mut snapshot = Id::root().snapshot();
+        snapshot.restore(0);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let mut decoded: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_bytes(), snapshot.to_bytes());
+        assert_eq!(decoded.restore(0).next_id(), snapshot.restore(0).next_id());
+    }
+}
@@ -0,0 +1,410 @@
+use core::fmt;
+
+use crate::{Id, SnowflakeError};
+
+/// A source of the current time, in milliseconds since the Unix epoch.
+///
+/// Implemented for closures, e.g. to test a [`SnowflakeGen`] with a fixed
+/// clock.
+pub trait Clock {
+    /// Returns the current time, in milliseconds since the Unix epoch.
+    fn now_ms(&self) -> u64;
+}
+
+impl<F: Fn() -> u64> Clock for F {
+    fn now_ms(&self) -> u64 {
+        self()
+    }
+}
+
+/// The system's clock.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64)
+    }
+}
+
+/// The layout of a 64-bit snowflake: from the most significant bit, a zero
+/// sign bit, the timestamp, the node and the sequence number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SnowflakeLayout {
+    epoch: u64,
+    timestamp_bits: u32,
+    node_bits: u32,
+    sequence_bits: u32,
+}
+
+impl SnowflakeLayout {
+    /// Twitter's layout: 41 bits of milliseconds since 2010-11-04, 10 bits
+    /// of node and 12 bits of sequence.
+    pub const TWITTER: Self = SnowflakeLayout {
+        epoch: 1_288_834_974_657,
+        timestamp_bits: 41,
+        node_bits: 10,
+        sequence_bits: 12,
+    };
+
+    /// Create a layout with the given epoch, in milliseconds since the Unix
+    /// epoch, and bit widths.
+    ///
+    /// Fails if the widths add up to more than 63 bits, or the timestamp or
+    /// sequence has none.
+    pub const fn new(
+        epoch: u64,
+        timestamp_bits: u32,
+        node_bits: u32,
+        sequence_bits: u32,
+    ) -> Result<Self, SnowflakeError> {
+        let fits = timestamp_bits <= 63
+            && node_bits <= 63
+            && sequence_bits <= 63
+            && timestamp_bits + node_bits + sequence_bits <= 63;
+
+        if !fits || timestamp_bits == 0 || sequence_bits == 0 {
+            return Err(SnowflakeError::InvalidLayout);
+        }
+
+        Ok(SnowflakeLayout {
+            epoch,
+            timestamp_bits,
+            node_bits,
+            sequence_bits,
+        })
+    }
+
+    /// Returns the epoch, in milliseconds since the Unix epoch.
+    pub const fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Returns the number of bits of the timestamp.
+    pub const fn timestamp_bits(&self) -> u32 {
+        self.timestamp_bits
+    }
+
+    /// Returns the number of bits of the node.
+    pub const fn node_bits(&self) -> u32 {
+        self.node_bits
+    }
+
+    /// Returns the number of bits of the sequence number.
+    pub const fn sequence_bits(&self) -> u32 {
+        self.sequence_bits
+    }
+
+    /// Split a snowflake back into its parts.
+    ///
+    /// Any `u64` can be decoded: bits outside the layout are ignored, and a
+    /// timestamp past `u64::MAX` wraps around.
+    ///
+    /// ```
+    /// use gens::SnowflakeLayout;
+    ///
+    /// let parts = SnowflakeLayout::TWITTER.decode(1_541_815_603_606_036_480);
+    /// assert_eq!(parts.timestamp, 1_656_432_460_105);
+    /// assert_eq!(parts.node, 378);
+    /// assert_eq!(parts.sequence, 0);
+    /// ```
+    pub const fn decode(&self, snowflake: u64) -> Snowflake {
+        Snowflake {
+            timestamp: self.epoch.wrapping_add(
+                (snowflake >> (self.node_bits + self.sequence_bits)) & mask(self.timestamp_bits),
+            ),
+            node: (snowflake >> self.sequence_bits) & mask(self.node_bits),
+            sequence: snowflake & mask(self.sequence_bits),
+        }
+    }
+
+    /// Join the parts of a snowflake, relative to the epoch.
+    const fn encode(&self, timestamp: u64, node: u64, sequence: u64) -> u64 {
+        timestamp << (self.node_bits + self.sequence_bits) | node << self.sequence_bits | sequence
+    }
+}
+
+impl Default for SnowflakeLayout {
+    fn default() -> Self {
+        Self::TWITTER
+    }
+}
+
+/// The parts of a snowflake, decoded with [`SnowflakeLayout::decode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Snowflake {
+    /// When the snowflake was generated, in milliseconds since the Unix
+    /// epoch.
+    pub timestamp: u64,
+
+    /// The node that generated the snowflake.
+    pub node: u64,
+
+    /// The snowflake's sequence number within its millisecond.
+    pub sequence: u64,
+}
+
+/// Generates 64-bit, Twitter-snowflake-style ID's, for systems that can't
+/// take 128-bit ones.
+///
+/// Each snowflake is a timestamp, the generator's node and a sequence
+/// number, laid out by a [`SnowflakeLayout`]. The node is taken from an
+/// [`Id`]'s [node](Id::node), which makes different nodes' snowflakes
+/// guaranteed to be distinct, so the `Id` must come from
+/// [`Id::root_for_node`]. Only one generator per node may run at a time.
+///
+/// Unlike the rest of this crate, snowflakes depend on time, read from a
+/// [`Clock`].
+///
+/// ```
+/// use std::cell::Cell;
+/// use gens::{Id, SnowflakeGen, SnowflakeLayout};
+///
+/// let layout = SnowflakeLayout::TWITTER;
+/// let now = Cell::new(layout.epoch() + 1000);
+/// let mut gen = SnowflakeGen::with_clock(&Id::root_for_node(5, 42), layout, || now.get())
+///     .unwrap();
+///
+/// let first = gen.next_id().unwrap();
+/// let second = gen.next_id().unwrap();
+/// assert!(first < second);
+///
+/// let parts = layout.decode(second);
+/// assert_eq!(parts.timestamp, layout.epoch() + 1000);
+/// assert_eq!(parts.node, 5);
+/// assert_eq!(parts.sequence, 1);
+/// ```
+pub struct SnowflakeGen<C> {
+    layout: SnowflakeLayout,
+    node: u64,
+    clock: C,
+    last: u64,
+    sequence: u64,
+}
+
+#[cfg(feature = "std")]
+impl SnowflakeGen<SystemClock> {
+    /// Create a generator for the given `Id`'s node, using the system's
+    /// clock. See [`SnowflakeGen::with_clock`].
+    pub fn new<D>(id: &Id<D>, layout: SnowflakeLayout) -> Result<Self, SnowflakeError> {
+        Self::with_clock(id, layout, SystemClock)
+    }
+}
+
+impl<C: Clock> SnowflakeGen<C> {
+    /// Create a generator for the given `Id`'s node, reading the time from
+    /// `clock`.
+    ///
+    /// Fails if the `Id` doesn't belong to a node, or if its node doesn't
+    /// fit in the layout.
+    pub fn with_clock<D>(
+        id: &Id<D>,
+        layout: SnowflakeLayout,
+        clock: C,
+    ) -> Result<Self, SnowflakeError> {
+        match id.node() {
+            None => Err(SnowflakeError::NoNode),
+            Some(node) if u64::from(node) > mask(layout.node_bits) => {
+                Err(SnowflakeError::NodeOutOfRange)
+            }
+            Some(node) => Ok(Self::with_node(node.into(), layout, clock)),
+        }
+    }
+
+    /// Create a generator for an `Id` that may not belong to a node, reading
+    /// the time from `clock`. Without a node, the generator uses the low
+    /// bits of the `Id`'s final ID instead.
+    ///
+    /// Careful: those bits only make different generators' snowflakes
+    /// *probably* distinct. With 10 node bits, two of 38 generators already
+    /// share a node about half the time. Prefer [`Id::root_for_node`] and
+    /// [`SnowflakeGen::with_clock`] wherever possible.
+    pub fn with_hashed_node<D>(
+        id: &Id<D>,
+        layout: SnowflakeLayout,
+        clock: C,
+    ) -> Result<Self, SnowflakeError> {
+        match id.node() {
+            None => Ok(Self::with_node(
+                id.id() as u64 & mask(layout.node_bits),
+                layout,
+                clock,
+            )),
+            Some(_) => Self::with_clock(id, layout, clock),
+        }
+    }
+
+    /// Create a generator for the given node, which must fit in the layout.
+    fn with_node(node: u64, layout: SnowflakeLayout, clock: C) -> Self {
+        SnowflakeGen {
+            layout,
+            node,
+            clock,
+            last: 0,
+            sequence: 0,
+        }
+    }
+
+    /// Generate the next snowflake.
+    ///
+    /// Fails if the clock is outside the layout's range or went backwards,
+    /// or if the current millisecond has no sequence numbers left.
+    pub fn next_id(&mut self) -> Result<u64, SnowflakeError> {
+        let timestamp = self
+            .clock
+            .now_ms()
+            .checked_sub(self.layout.epoch)
+            .filter(|&timestamp| timestamp <= mask(self.layout.timestamp_bits))
+            .ok_or(SnowflakeError::TimestampOutOfRange)?;
+
+        if timestamp < self.last {
+            return Err(SnowflakeError::ClockBackwards);
+        }
+
+        if timestamp > self.last {
+            self.last = timestamp;
+            self.sequence = 0;
+        }
+
+        if self.sequence > mask(self.layout.sequence_bits) {
+            return Err(SnowflakeError::SequenceExhausted);
+        }
+
+        let snowflake = self.layout.encode(timestamp, self.node, self.sequence);
+        self.sequence += 1;
+        Ok(snowflake)
+    }
+}
+
+impl<C> SnowflakeGen<C> {
+    /// Returns the layout of the snowflakes.
+    pub fn layout(&self) -> SnowflakeLayout {
+        self.layout
+    }
+
+    /// Returns the node part of the snowflakes.
+    pub fn node(&self) -> u64 {
+        self.node
+    }
+}
+
+impl<C> fmt::Debug for SnowflakeGen<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnowflakeGen")
+            .field("layout", &self.layout)
+            .field("node", &self.node)
+            .field("last", &self.last)
+            .field("sequence", &self.sequence)
+            .finish()
+    }
+}
+
+/// Returns a mask of the lowest `bits` bits.
+const fn mask(bits: u32) -> u64 {
+    if bits == 0 {
+        0
+    } else {
+        u64::MAX >> (64 - bits)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::collections::HashSet;
+
+    use crate::{Id, SnowflakeError, SnowflakeGen, SnowflakeLayout};
+
+    use super::mask;
+
+    #[test]
+    fn sequence_and_time() {
+        let layout = SnowflakeLayout::new(1000, 20, 4, 3).unwrap();
+        let now = Cell::new(1000);
+        let mut gen =
+            SnowflakeGen::with_clock(&Id::root_for_node(9, 25), layout, || now.get()).unwrap();
+
+        let mut set = HashSet::new();
+        let mut last = 0;
+        for ms in 0..10 {
+            now.set(1000 + ms);
+            for sequence in 0..8 {
+                let id = gen.next_id().unwrap();
+                assert!(id > last || (ms, sequence) == (0, 0));
+                assert!(set.insert(id));
+                last = id;
+
+                let parts = layout.decode(id);
+                assert_eq!(parts.timestamp, 1000 + ms);
+                assert_eq!(parts.node, 9);
+                assert_eq!(parts.sequence, sequence);
+            }
+            assert_eq!(gen.next_id(), Err(SnowflakeError::SequenceExhausted));
+        }
+
+        now.set(1005);
+        assert_eq!(gen.next_id(), Err(SnowflakeError::ClockBackwards));
+        now.set(999);
+        assert_eq!(gen.next_id(), Err(SnowflakeError::TimestampOutOfRange));
+        now.set(1000 + (1 << 20));
+        assert_eq!(gen.next_id(), Err(SnowflakeError::TimestampOutOfRange));
+    }
+
+    #[test]
+    fn node() {
+        let layout = SnowflakeLayout::new(0, 40, 8, 15).unwrap();
+        let clock = || 1;
+
+        let gen = SnowflakeGen::with_clock(&Id::root_for_node(255, 25), layout, clock).unwrap();
+        assert_eq!(gen.node(), 255);
+        assert_eq!(
+            SnowflakeGen::with_clock(&Id::root_for_node(256, 25), layout, clock).err(),
+            Some(SnowflakeError::NodeOutOfRange)
+        );
+
+        // Without a node, only if hashed nodes are opted into
+        let mut root = Id::root_with_seed(25);
+        let child = root.next_id();
+        assert_eq!(
+            SnowflakeGen::with_clock(&child, layout, clock).err(),
+            Some(SnowflakeError::NoNode)
+        );
+        let mut gen = SnowflakeGen::with_hashed_node(&child, layout, clock).unwrap();
+        assert_eq!(gen.node(), child.id() as u64 & 0xff);
+        assert_eq!(layout.decode(gen.next_id().unwrap()).node, gen.node());
+    }
+
+    #[test]
+    fn layout() {
+        assert!(SnowflakeLayout::new(0, 41, 10, 12).is_ok());
+        assert!(SnowflakeLayout::new(0, 63, 0, 0).is_err());
+        assert!(SnowflakeLayout::new(0, 0, 10, 12).is_err());
+        assert!(SnowflakeLayout::new(0, 42, 10, 12).is_err());
+        assert!(SnowflakeLayout::new(0, 1, u32::MAX, 1).is_err());
+        assert_eq!(SnowflakeLayout::default(), SnowflakeLayout::TWITTER);
+
+        let twitter = SnowflakeLayout::TWITTER;
+        let id = twitter.encode(1_656_432_460_105 - twitter.epoch(), 378, 7);
+        assert_eq!(id, 1_541_815_603_606_036_487);
+        assert_eq!(twitter.decode(id).timestamp, 1_656_432_460_105);
+
+        // The sign bit isn't part of the timestamp
+        assert_eq!(twitter.decode(id | 1 << 63), twitter.decode(id));
+        let late = SnowflakeLayout::new(u64::MAX, 41, 10, 12).unwrap();
+        assert_eq!(late.decode(u64::MAX).timestamp, mask(41) - 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn system_clock() {
+        let mut gen =
+            SnowflakeGen::new(&Id::root_for_node(1, 25), SnowflakeLayout::TWITTER).unwrap();
+        let parts = SnowflakeLayout::TWITTER.decode(gen.next_id().unwrap());
+        assert_eq!(parts.node, 1);
+        assert!(parts.timestamp > 1_700_000_000_000);
+    }
+}
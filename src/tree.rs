@@ -0,0 +1,349 @@
+use alloc::collections::btree_map::Entry;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+
+use crate::{Fnv, Id, IdDeriver};
+
+/// The order [`IdTree::descendants_of`] visits `Id`s in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Traversal {
+    /// Visit every `Id` at one depth before moving on to the next.
+    #[default]
+    BreadthFirst,
+
+    /// Visit every descendant of an `Id` before moving on to its next
+    /// sibling (pre-order).
+    DepthFirst,
+}
+
+struct Node<D> {
+    id: Id<D>,
+    parent: Option<u128>,
+    children: Vec<u128>,
+}
+
+/// A registry of every [`Id`] minted through it, with links between parents
+/// and children.
+///
+/// `Id`s are looked up by their final ID. The tree owns every generator, so
+/// the full `Id` of any final ID can be looked up and more children can be
+/// minted from it later.
+///
+/// ```
+/// use gens::{Id, IdTree, Traversal};
+///
+/// let mut tree = IdTree::new(Id::root());
+/// let root = tree.root();
+/// let a = tree.next_id(root).unwrap();
+/// let b = tree.next_id(root).unwrap();
+/// let a1 = tree.next_id(a).unwrap();
+///
+/// assert_eq!(tree.children_of(root), Some(&[a, b][..]));
+/// assert_eq!(tree.parent_of(a1), Some(a));
+/// assert_eq!(tree.subtree_size(root), 4);
+///
+/// let bfs: Vec<_> = tree.descendants_of(root, Traversal::BreadthFirst).collect();
+/// assert_eq!(bfs, [a, b, a1]);
+///
+/// let removed = tree.remove_subtree(a);
+/// assert_eq!(removed.len(), 2);
+/// assert_eq!(tree.children_of(root), Some(&[b][..]));
+/// ```
+pub struct IdTree<D = Fnv> {
+    root: u128,
+    nodes: BTreeMap<u128, Node<D>>,
+}
+
+impl<D> IdTree<D> {
+    /// Create a tree containing only `root`.
+    ///
+    /// `root` doesn't have to be a root-level `Id`; any `Id` works.
+    pub fn new(root: Id<D>) -> Self {
+        let id = root.id();
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            id,
+            Node {
+                id: root,
+                parent: None,
+                children: Vec::new(),
+            },
+        );
+
+        IdTree { root: id, nodes }
+    }
+
+    /// Returns the final ID of the `Id` this tree was created with.
+    pub fn root(&self) -> u128 {
+        self.root
+    }
+
+    /// Returns the number of `Id`s in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether the tree is empty, i.e. its root was removed.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns whether the `Id` with the given final ID is in the tree.
+    pub fn contains(&self, id: u128) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    /// Returns the full `Id` with the given final ID.
+    pub fn get(&self, id: u128) -> Option<&Id<D>> {
+        self.nodes.get(&id).map(|node| &node.id)
+    }
+
+    /// Returns the final ID's of the children minted from the given `Id`,
+    /// oldest first.
+    pub fn children_of(&self, id: u128) -> Option<&[u128]> {
+        self.nodes.get(&id).map(|node| node.children.as_slice())
+    }
+
+    /// Returns the final ID of the parent of the given `Id`; `None` for the
+    /// root, or for an `Id` that isn't in the tree.
+    pub fn parent_of(&self, id: u128) -> Option<u128> {
+        self.nodes.get(&id).and_then(|node| node.parent)
+    }
+
+    /// Returns an iterator over the final ID's of every descendant of the
+    /// given `Id`, not including itself.
+    pub fn descendants_of(&self, id: u128, traversal: Traversal) -> Descendants<'_, D> {
+        let mut pending = VecDeque::new();
+        if let Some(node) = self.nodes.get(&id) {
+            pending.extend(&node.children);
+        }
+
+        Descendants {
+            tree: self,
+            pending,
+            traversal,
+        }
+    }
+
+    /// Returns the number of `Id`s in the subtree of the given `Id`,
+    /// including itself; 0 if it isn't in the tree.
+    pub fn subtree_size(&self, id: u128) -> usize {
+        if self.contains(id) {
+            1 + self.descendants_of(id, Traversal::DepthFirst).count()
+        } else {
+            0
+        }
+    }
+
+    /// Removes the given `Id` and all of its descendants from the tree,
+    /// returning them breadth-first. Removing the root empties the tree.
+    ///
+    /// The returned `Id`s can keep minting children, just not through the
+    /// tree.
+    pub fn remove_subtree(&mut self, id: u128) -> Vec<Id<D>> {
+        let Some(node) = self.nodes.get(&id) else {
+            return Vec::new();
+        };
+
+        if let Some(parent) = node.parent.and_then(|parent| self.nodes.get_mut(&parent)) {
+            parent.children.retain(|&child| child != id);
+        }
+
+        let mut removed = Vec::new();
+        let mut pending = VecDeque::from([id]);
+        while let Some(id) = pending.pop_front() {
+            if let Some(node) = self.nodes.remove(&id) {
+                pending.extend(node.children);
+                removed.push(node.id);
+            }
+        }
+
+        removed
+    }
+}
+
+impl<D: IdDeriver> IdTree<D> {
+    /// Mint a new child of the given `Id` and record it, returning its final
+    /// ID. See [`Id::try_next_id`].
+    ///
+    /// Returns `None` if the `Id` isn't in the tree, can't mint any more
+    /// children, or minted a child whose final ID is already in the tree. In
+    /// the last case, the tree is left unchanged, but the parent moves on to
+    /// its next child.
+    pub fn next_id(&mut self, parent: u128) -> Option<u128> {
+        let node = self.nodes.get_mut(&parent)?;
+        let child = node.id.try_next_id().ok()?;
+        let id = child.id();
+
+        let Entry::Vacant(entry) = self.nodes.entry(id) else {
+            return None;
+        };
+        entry.insert(Node {
+            id: child,
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+
+        // The parent's node has to be looked up again after the insertion
+        self.nodes.get_mut(&parent)?.children.push(id);
+        Some(id)
+    }
+}
+
+impl<D> fmt::Debug for IdTree<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdTree")
+            .field("root", &self.root)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// An iterator over the final ID's of an `Id`'s descendants in an [`IdTree`].
+///
+/// Created by [`IdTree::descendants_of`].
+pub struct Descendants<'a, D = Fnv> {
+    tree: &'a IdTree<D>,
+    pending: VecDeque<u128>,
+    traversal: Traversal,
+}
+
+impl<D> Iterator for Descendants<'_, D> {
+    type Item = u128;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.pending.pop_front()?;
+        let children = self.tree.children_of(id).unwrap_or_default();
+
+        match self.traversal {
+            Traversal::BreadthFirst => self.pending.extend(children),
+            Traversal::DepthFirst => {
+                for &child in children.iter().rev() {
+                    self.pending.push_front(child);
+                }
+            }
+        }
+
+        Some(id)
+    }
+}
+
+impl<D> FusedIterator for Descendants<'_, D> {}
+
+impl<D> fmt::Debug for Descendants<'_, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Descendants")
+            .field("pending", &self.pending)
+            .field("traversal", &self.traversal)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Id, IdDeriver, IdTree, Traversal};
+
+    #[test]
+    fn links_and_traversal() {
+        let mut tree = IdTree::new(Id::root_with_seed(13));
+        let root = tree.root();
+
+        let a = tree.next_id(root).unwrap();
+        let b = tree.next_id(root).unwrap();
+        let a1 = tree.next_id(a).unwrap();
+        let a2 = tree.next_id(a).unwrap();
+        let b1 = tree.next_id(b).unwrap();
+        let a11 = tree.next_id(a1).unwrap();
+
+        assert_eq!(tree.len(), 7);
+        assert_eq!(tree.parent_of(root), None);
+        assert_eq!(tree.parent_of(a11), Some(a1));
+        assert_eq!(tree.children_of(a), Some(&[a1, a2][..]));
+        assert_eq!(tree.children_of(a11), Some(&[][..]));
+        assert_eq!(tree.children_of(0xdead), None);
+        assert_eq!(tree.next_id(0xdead), None);
+
+        let bfs: Vec<_> = tree.descendants_of(root, Traversal::BreadthFirst).collect();
+        assert_eq!(bfs, [a, b, a1, a2, b1, a11]);
+        let dfs: Vec<_> = tree.descendants_of(root, Traversal::DepthFirst).collect();
+        assert_eq!(dfs, [a, a1, a11, a2, b, b1]);
+
+        assert_eq!(tree.subtree_size(root), 7);
+        assert_eq!(tree.subtree_size(a), 4);
+        assert_eq!(tree.subtree_size(a11), 1);
+        assert_eq!(tree.subtree_size(0xdead), 0);
+    }
+
+    #[test]
+    fn rehydrate() {
+        let mut tree = IdTree::new(Id::root());
+        let root = tree.root();
+        let a = tree.next_id(root).unwrap();
+
+        // The stored generators are the same ones `Id::next_id` produces
+        let mut expected = Id::root();
+        let mut expected_a = expected.next_id();
+        assert_eq!(tree.get(a), Some(&expected_a));
+
+        let a1 = tree.next_id(a).unwrap();
+        assert_eq!(a1, expected_a.next_id().id());
+        assert_eq!(tree.get(a).unwrap().num_children(), 1);
+        assert_eq!(tree.get(root).unwrap().num_children(), 1);
+    }
+
+    #[test]
+    fn remove_subtree() {
+        let mut tree = IdTree::new(Id::root());
+        let root = tree.root();
+        let a = tree.next_id(root).unwrap();
+        let b = tree.next_id(root).unwrap();
+        let a1 = tree.next_id(a).unwrap();
+
+        let mut removed = tree.remove_subtree(a);
+        assert_eq!(removed.iter().map(Id::id).collect::<Vec<_>>(), [a, a1]);
+        assert!(!tree.contains(a) && !tree.contains(a1));
+        assert_eq!(tree.children_of(root), Some(&[b][..]));
+        assert!(tree.remove_subtree(a).is_empty());
+
+        // Removed generators keep going where they left off
+        assert_ne!(removed[0].next_id().id(), a1);
+
+        assert_eq!(tree.remove_subtree(root).len(), 2);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn no_overwrites() {
+        /// A deriver that gives every child the same ID.
+        struct Constant;
+
+        impl IdDeriver for Constant {
+            const TAG: u64 = 13;
+
+            fn derive(_: &[u8]) -> u64 {
+                13
+            }
+        }
+
+        let mut tree = IdTree::new(Id::<Constant>::root_with_deriver());
+        let root = tree.root();
+        let a = tree.next_id(root).unwrap();
+        let a1 = tree.next_id(a).unwrap();
+
+        // The second child repeats the first one's final ID
+        assert_eq!(tree.next_id(root), None);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.children_of(root), Some(&[a][..]));
+        assert_eq!(tree.children_of(a), Some(&[a1][..]));
+        assert_eq!(tree.get(root).unwrap().num_children(), 2);
+
+        // A parent that's out of children
+        let mut full = Id::root_with_seed(13);
+        full.gen = u32::MAX as u64;
+        let mut tree = IdTree::new(full);
+        assert_eq!(tree.next_id(tree.root()), None);
+        assert_eq!(tree.len(), 1);
+    }
+}
@@ -0,0 +1,346 @@
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{FinalId, Fnv, GenError, Id, IdDeriver, ParseIdError};
+
+/// An [`Id`] tagged with the type of entity it identifies, e.g.
+/// `TypedId<User>`.
+///
+/// A `TypedId<T>` only mints children of type `T`, and its final ID is a
+/// [`TypedFinalId<T>`], so the ID's of different entities minted from the
+/// same tree can't be mixed up. `T` is only a marker: it's never
+/// constructed, and a `TypedId` has the same size and layout as an `Id`.
+///
+/// Use [`cast`](TypedId::cast) to fork a generator for another type of
+/// entity off a tree.
+///
+/// ```
+/// use gens::{Id, TypedFinalId, TypedId};
+///
+/// struct User;
+/// struct Order;
+///
+/// let mut root = Id::root();
+/// let mut users: TypedId<User> = TypedId::new(root.next_id());
+/// let mut orders: TypedId<Order> = root.next_id().into();
+///
+/// fn cancel(order: TypedFinalId<Order>) { /* ... */ }
+///
+/// let order = orders.next_id().final_id();
+/// let user = users.next_id().final_id();
+/// cancel(order);
+/// // cancel(user); // error: expected `TypedFinalId<Order>`
+/// assert_ne!(user.get(), order.get());
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent, bound = ""))]
+#[repr(transparent)]
+pub struct TypedId<T, D = Fnv> {
+    id: Id<D>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    entity: PhantomData<fn() -> T>,
+}
+
+impl<T, D> TypedId<T, D> {
+    /// Tag an `Id` with the type of entity it identifies.
+    pub fn new(id: Id<D>) -> Self {
+        TypedId {
+            id,
+            entity: PhantomData,
+        }
+    }
+
+    /// Returns the underlying `Id`.
+    pub fn as_id(&self) -> &Id<D> {
+        &self.id
+    }
+
+    /// Discards the type, returning the underlying `Id`.
+    pub fn into_id(self) -> Id<D> {
+        self.id
+    }
+
+    /// Re-tag this `Id` as another type of entity; its children will be of
+    /// type `U` too.
+    pub fn cast<U>(self) -> TypedId<U, D> {
+        TypedId::new(self.id)
+    }
+
+    /// Returns the numerical ID for use. See [`Id::id`].
+    pub fn id(&self) -> u128 {
+        self.id.id()
+    }
+
+    /// Returns the typed final ID. See [`Id::final_id`].
+    pub fn final_id(&self) -> TypedFinalId<T> {
+        TypedFinalId::new(self.id.final_id())
+    }
+
+    /// Returns the final ID of the parent. Since a tree may contain several
+    /// types of entities, it's untyped.
+    pub fn parent(&self) -> FinalId {
+        FinalId::new(self.id.parent())
+    }
+
+    /// Returns the depth. See [`Id::depth`].
+    pub fn depth(&self) -> u32 {
+        self.id.depth()
+    }
+}
+
+impl<T, D: IdDeriver> TypedId<T, D> {
+    /// Generate a new, unique child of the same type. See [`Id::next_id`].
+    pub fn next_id(&mut self) -> Self {
+        TypedId::new(self.id.next_id())
+    }
+
+    /// Generate a new, unique child of the same type. See
+    /// [`Id::try_next_id`].
+    pub fn try_next_id(&mut self) -> Result<Self, GenError> {
+        self.id.try_next_id().map(TypedId::new)
+    }
+}
+
+impl<T, D> From<Id<D>> for TypedId<T, D> {
+    fn from(id: Id<D>) -> Self {
+        TypedId::new(id)
+    }
+}
+
+impl<T, D> From<TypedId<T, D>> for Id<D> {
+    fn from(id: TypedId<T, D>) -> Self {
+        id.id
+    }
+}
+
+impl<T, D> PartialEq for TypedId<T, D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T, D> Eq for TypedId<T, D> {}
+
+impl<T, D> PartialOrd for TypedId<T, D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, D> Ord for TypedId<T, D> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl<T, D> Hash for TypedId<T, D> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<T, D> fmt::Debug for TypedId<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.id, f)
+    }
+}
+
+impl<T, D> fmt::Display for TypedId<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.id, f)
+    }
+}
+
+/// A [`FinalId`] tagged with the type of entity it identifies, e.g.
+/// `TypedFinalId<User>`. See [`TypedId`].
+///
+/// Like `FinalId`, this hashes the same way as `u128` and implements
+/// `Borrow<FinalId>` and `Borrow<u128>`, so maps keyed by it can be queried
+/// with untyped ID's.
+///
+/// ```compile_fail
+/// use gens::{Id, TypedFinalId, TypedId};
+///
+/// struct User;
+/// struct Order;
+///
+/// fn cancel(order: TypedFinalId<Order>) { /* ... */ }
+///
+/// let mut users: TypedId<User> = Id::root().into();
+/// cancel(users.next_id().final_id());
+/// ```
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent, bound = ""))]
+#[repr(transparent)]
+pub struct TypedFinalId<T> {
+    id: FinalId,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    entity: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedFinalId<T> {
+    /// Tag a final ID with the type of entity it identifies.
+    pub const fn new(id: FinalId) -> Self {
+        TypedFinalId {
+            id,
+            entity: PhantomData,
+        }
+    }
+
+    /// Returns the untyped final ID.
+    pub const fn get(self) -> FinalId {
+        self.id
+    }
+
+    /// Re-tag this final ID as another type of entity.
+    pub const fn cast<U>(self) -> TypedFinalId<U> {
+        TypedFinalId::new(self.id)
+    }
+}
+
+impl<T> From<TypedFinalId<T>> for FinalId {
+    fn from(id: TypedFinalId<T>) -> Self {
+        id.id
+    }
+}
+
+impl<T> From<TypedFinalId<T>> for u128 {
+    fn from(id: TypedFinalId<T>) -> Self {
+        id.id.get()
+    }
+}
+
+impl<T> Borrow<FinalId> for TypedFinalId<T> {
+    fn borrow(&self) -> &FinalId {
+        &self.id
+    }
+}
+
+impl<T> Borrow<u128> for TypedFinalId<T> {
+    fn borrow(&self) -> &u128 {
+        self.id.borrow()
+    }
+}
+
+impl<T> Clone for TypedFinalId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedFinalId<T> {}
+
+impl<T> PartialEq for TypedFinalId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for TypedFinalId<T> {}
+
+impl<T> PartialOrd for TypedFinalId<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for TypedFinalId<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl<T> Hash for TypedFinalId<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<T> FromStr for TypedFinalId<T> {
+    type Err = ParseIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(TypedFinalId::new)
+    }
+}
+
+impl<T> fmt::Debug for TypedFinalId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.id, f)
+    }
+}
+
+impl<T> fmt::Display for TypedFinalId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.id, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::mem::{align_of, size_of};
+
+    use crate::{FinalId, Id, TypedFinalId, TypedId};
+
+    struct User;
+    struct Session;
+
+    #[test]
+    fn zero_cost() {
+        assert_eq!(size_of::<TypedId<User>>(), size_of::<Id>());
+        assert_eq!(align_of::<TypedId<User>>(), align_of::<Id>());
+        assert_eq!(size_of::<TypedFinalId<User>>(), size_of::<FinalId>());
+        assert_eq!(
+            size_of::<Option<TypedFinalId<User>>>(),
+            size_of::<Option<u128>>()
+        );
+    }
+
+    #[test]
+    fn same_ids_as_untyped() {
+        let mut root = Id::root_with_seed(17);
+        let mut users: TypedId<User> = root.next_id().into();
+        let mut sessions = users.next_id().cast::<Session>();
+
+        let mut expected_root = Id::root_with_seed(17);
+        let mut expected_users = expected_root.next_id();
+        let mut expected_sessions = expected_users.next_id();
+
+        assert_eq!(users.as_id(), &expected_users);
+        assert_eq!(users.depth(), 1);
+        assert_eq!(sessions.parent(), expected_users.final_id());
+
+        let user = users.next_id();
+        let session = sessions.next_id();
+        assert_eq!(user.final_id().get(), expected_users.next_id().final_id());
+        assert_eq!(session.into_id(), expected_sessions.next_id());
+        assert_eq!(
+            users.try_next_id().unwrap().id(),
+            expected_users.next_id().id()
+        );
+    }
+
+    #[test]
+    fn final_ids() {
+        let mut users: TypedId<User> = TypedId::new(Id::root());
+        let user = users.next_id().final_id();
+
+        let mut set = HashSet::new();
+        set.insert(user);
+        assert!(set.contains(&user.get()));
+        assert!(set.contains(&user.get().get()));
+
+        assert_eq!(user.to_string(), user.get().to_string());
+        assert_eq!(user.to_string().parse(), Ok(user));
+        assert_eq!(user.cast::<Session>().get(), user.get());
+        assert_eq!(u128::from(user), user.get().get());
+    }
+}
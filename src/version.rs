@@ -0,0 +1,61 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The algorithm an [`Id`](crate::Id) uses to derive its children.
+///
+/// Every child inherits its parent's version. Versions never change once
+/// released, so a tree created with a given version can always be
+/// reproduced, no matter the platform or the release of this crate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum Version {
+    /// The original algorithm, which hashes fields in native byte order.
+    ///
+    /// Big- and little-endian targets produce different trees, so this is
+    /// only useful to reproduce trees created before versioning existed.
+    V0 = 0,
+
+    /// Hashes fields in little-endian byte order.
+    ///
+    /// Produces the same trees as [`Version::V0`] on little-endian targets.
+    V1 = 1,
+
+    /// Hashes fields like [`Version::V1`], but mixes the final ID properly.
+    ///
+    /// Earlier versions compute the final ID as `(depth + 1) * (parent ^ id)`,
+    /// which throws away high bits whenever `depth + 1` is even, so distinct
+    /// `Id`s can end up with the same final ID. `V2` instead passes
+    /// `parent ^ (depth << 64 | id)` through a bijective 128-bit mix, so every
+    /// bit of the parent counts and siblings only share a final ID if their
+    /// hashes collide.
+    ///
+    /// Since a child's state includes its parent's final ID, this changes
+    /// every tree below the root. If final ID's from an earlier version were
+    /// already persisted, keep extending those trees with that version via
+    /// [`Id::with_version`](crate::Id::with_version).
+    #[default]
+    V2 = 2,
+}
+
+impl Version {
+    /// The newest version, used by default.
+    pub const LATEST: Version = Version::V2;
+
+    /// Returns the version with the given numerical representation.
+    pub(crate) fn from_repr(repr: u8) -> Option<Version> {
+        match repr {
+            0 => Some(Version::V0),
+            1 => Some(Version::V1),
+            2 => Some(Version::V2),
+            _ => None,
+        }
+    }
+
+    /// The version of `Id`s serialized before versioning existed.
+    #[cfg(feature = "serde")]
+    pub(crate) fn legacy() -> Version {
+        Version::V0
+    }
+}
@@ -0,0 +1,55 @@
+//! Model-checks [`SharedId`] under every interleaving of a few threads.
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --test loom --release`.
+
+#![cfg(loom)]
+
+use loom::sync::Arc;
+use loom::thread;
+
+use gens::{Id, SharedId};
+
+#[test]
+fn concurrent_children_are_unique() {
+    loom::model(|| {
+        let shared = Arc::new(SharedId::new(Id::root()));
+
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || shared.next_id().id())
+            })
+            .collect();
+
+        let mut ids = vec![shared.next_id().id()];
+        ids.extend(handles.into_iter().map(|h| h.join().unwrap()));
+        ids.sort_unstable();
+        ids.dedup();
+
+        assert_eq!(ids.len(), 3);
+        assert_eq!(shared.num_children(), 3);
+    });
+}
+
+#[test]
+fn into_inner_keeps_children() {
+    loom::model(|| {
+        let shared = Arc::new(SharedId::new(Id::root()));
+
+        let handle = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.next_id())
+        };
+
+        let first = shared.next_id();
+        let second = handle.join().unwrap();
+        assert_ne!(first, second);
+
+        let mut root = Arc::try_unwrap(shared).unwrap().into_inner();
+        assert_eq!(root.num_children(), 2);
+
+        let third = root.next_id();
+        assert_ne!(third, first);
+        assert_ne!(third, second);
+    });
+}
@@ -0,0 +1,154 @@
+//! Runs a [`Server`] on localhost and talks to it over real sockets.
+
+#![cfg(feature = "server")]
+
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use gens::server::{Client, Server};
+use gens::{Id, PersistentId};
+
+struct TempPath(PathBuf);
+
+impl TempPath {
+    fn new(extension: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "gens-server-{}-{}.{extension}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        TempPath(std::env::temp_dir().join(name))
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Start a server on a free port, returning its address.
+fn start(state: &TempPath) -> SocketAddr {
+    let id = PersistentId::open_with_block_size(&state.0, Id::root_with_seed(23), 100).unwrap();
+    let server = Server::new(id);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    thread::spawn(move || server.serve_tcp(&listener));
+    address
+}
+
+#[test]
+fn clients_get_unique_ids() {
+    let state = TempPath::new("state");
+    let address = start(&state);
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut client = Client::new(TcpStream::connect(address).unwrap()).with_batch(10);
+                let mut ids: Vec<_> = (0..50).map(|_| client.next_id().unwrap().id()).collect();
+                ids.push(client.fetch_id().unwrap().id());
+                ids
+            })
+        })
+        .collect();
+
+    let mut set = HashSet::new();
+    for handle in handles {
+        for id in handle.join().unwrap() {
+            assert!(set.insert(id));
+        }
+    }
+
+    // Exactly the children of the root, as if it generated them itself
+    let mut root = Id::root_with_seed(23);
+    let expected: HashSet<_> = (0..4 * 51).map(|_| root.next_id().id()).collect();
+    assert_eq!(set, expected);
+}
+
+#[test]
+fn client_caches_leases() {
+    let state = TempPath::new("state");
+    let mut client = Client::new(TcpStream::connect(start(&state)).unwrap()).with_batch(5);
+
+    assert_eq!(client.cached(), 0);
+    let first = client.next_id().unwrap();
+    assert_eq!(client.cached(), 4);
+
+    let mut lease = client.lease(3).unwrap();
+    assert_eq!(client.cached(), 4);
+    for _ in 0..4 {
+        client.next_id().unwrap();
+    }
+
+    // The cached batch was leased first
+    let mut root = Id::root_with_seed(23);
+    assert_eq!(first, root.next_id());
+    root.lease(4).unwrap();
+    assert_eq!(lease.next_id().unwrap(), root.next_id());
+
+    client.next_id().unwrap();
+    assert_eq!(client.cached(), 4);
+}
+
+#[test]
+fn errors() {
+    let state = TempPath::new("state");
+    let address = start(&state);
+
+    let mut client = Client::new(TcpStream::connect(address).unwrap());
+    let err = client.lease(u64::MAX).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    assert!(client.next_id().is_ok());
+    assert!(client.lease(u32::MAX as u64).is_err());
+    assert!(client.lease(1).is_ok());
+
+    // An invalid request is answered with an error
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(&[1, 0, 0, 0, 7]).unwrap();
+    let mut len = [0; 4];
+    stream.read_exact(&mut len).unwrap();
+    let mut response = vec![0; u32::from_le_bytes(len) as usize];
+    stream.read_exact(&mut response).unwrap();
+    assert_eq!(response, b"\x01invalid request");
+
+    // An oversized frame closes the connection
+    stream.write_all(&u32::MAX.to_le_bytes()).unwrap();
+    let mut rest = Vec::new();
+    assert!(stream.read_to_end(&mut rest).map_or(true, |n| n == 0));
+}
+
+#[test]
+fn state_file_locked() {
+    let state = TempPath::new("state");
+    start(&state);
+
+    // Another server can't hand out the same children
+    let id = PersistentId::open(&state.0, Id::root_with_seed(23));
+    assert_eq!(id.unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+}
+
+#[test]
+#[cfg(unix)]
+fn unix_socket() {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let state = TempPath::new("state");
+    let socket = TempPath::new("sock");
+    let id = PersistentId::open(&state.0, Id::root_with_seed(23)).unwrap();
+    let server = Server::new(id);
+    let listener = UnixListener::bind(&socket.0).unwrap();
+    thread::spawn(move || server.serve_unix(&listener));
+
+    let mut client = Client::new(UnixStream::connect(&socket.0).unwrap());
+    let mut root = Id::root_with_seed(23);
+    for _ in 0..100 {
+        assert_eq!(client.next_id().unwrap(), root.next_id());
+    }
+}